@@ -1,4 +1,34 @@
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+// The protos described by the reflection service
+const PROTOS: &[&str] = &[
+    "proto/packybara.proto",
+    "proto/health.proto",
+    "proto/reflection.proto",
+];
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_build::compile_protos("proto/packybara.proto")?;
+    for proto in PROTOS {
+        tonic_build::compile_protos(proto)?;
+    }
+    // tonic-build does not emit a file descriptor set, so we ask the
+    // protoc bundled with prost-build for one, including the imported
+    // well known types so that the set is self contained.
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+    let status = Command::new(prost_build::protoc())
+        .arg("--include_imports")
+        .arg("-o")
+        .arg(out_dir.join("packybara_descriptor.bin"))
+        .arg("-I")
+        .arg("proto")
+        .arg("-I")
+        .arg(prost_build::protoc_include())
+        .args(PROTOS)
+        .status()?;
+    if !status.success() {
+        return Err(format!("protoc failed to write the descriptor set: {}", status).into());
+    }
     Ok(())
 }
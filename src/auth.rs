@@ -0,0 +1,427 @@
+//! Authentication and authorization of requests.
+//!
+//! Authentication is performed by an interceptor installed in front of the
+//! service. The interceptor hands the request's metadata to an
+//! `Authenticator`, and records the name of the user it identifies in the
+//! `x-packybara-user` metadata of the request, replacing any value supplied by
+//! the client. `StaticTokens`, which maps bearer tokens read from a file to
+//! users, is provided; other schemes may be plugged in by implementing
+//! `Authenticator`.
+//!
+//! Authorization is governed by a `Policy`, which grants users write access
+//! to levels (and the levels beneath them), or to everything. The service
+//! consults the policy before applying any mutation.
+//!
+//! # Tokens File
+//!
+//! ```toml
+//! [tokens]
+//! jgerber = "6f1c0a9e3e2b4d27"
+//! build = "b41d0f1e2c8a9b37"
+//! ```
+//!
+//! # Policy File
+//!
+//! ```toml
+//! # users who may write anything, including packages, roles and platforms
+//! admins = ["jgerber"]
+//!
+//! # users who may write at a level, and the levels beneath it. "*" names
+//! # every authenticated user.
+//! [levels]
+//! dev01 = ["alice", "bob"]
+//! "dev01.rd" = ["carol"]
+//! sandbox = ["*"]
+//! ```
+use serde::Deserialize;
+use snafu::{ResultExt, Snafu};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::{Code, Interceptor, Request, Status};
+
+/// Metadata key carrying the client's credentials
+pub const AUTHORIZATION_KEY: &str = "authorization";
+/// Metadata key carrying the name of the authenticated user
+pub const USER_KEY: &str = "x-packybara-user";
+/// Grants a level to every authenticated user
+pub const ANY_USER: &str = "*";
+
+/// Errors encountered while loading the tokens or policy files
+#[derive(Debug, Snafu)]
+pub enum AuthError {
+    #[snafu(display("Unable to read {}: {}", path.display(), source))]
+    ReadFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Unable to parse {}: {}", path.display(), source))]
+    ParseFile {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+}
+
+/// Identifies the user making a request from the request's metadata.
+/// Implement this trait to plug in an authentication scheme.
+pub trait Authenticator: Send + Sync + 'static {
+    /// Authenticate a request, returning the name of the user it was made by.
+    /// A request which cannot be authenticated should result in an
+    /// Unauthenticated Status.
+    fn authenticate(&self, metadata: &MetadataMap) -> Result<String, Status>;
+}
+
+/// Converts an Authenticator into an interceptor which may be installed in
+/// front of the service.
+pub trait AuthenticatorExt: Authenticator + Sized {
+    /// Build an interceptor which authenticates each request, recording the
+    /// user in its metadata.
+    fn into_interceptor(self) -> Interceptor {
+        let auth = Arc::new(self);
+        let intercept = move |request: Request<()>| authenticate_request(auth.as_ref(), request);
+        intercept.into()
+    }
+}
+
+impl<A: Authenticator> AuthenticatorExt for A {}
+
+/// Authenticate a request, recording the user it was made by under
+/// `USER_KEY`. Any user supplied by the client is discarded.
+///
+/// # Arguments
+///
+/// * `auth` - The Authenticator
+/// * `request` - The request, stripped of its message
+///
+/// # Returns
+///
+/// * Result
+/// - Ok - The request
+/// - Err - Status
+pub fn authenticate_request<A>(auth: &A, mut request: Request<()>) -> Result<Request<()>, Status>
+where
+    A: Authenticator + ?Sized,
+{
+    request.metadata_mut().remove(USER_KEY);
+    let user = auth.authenticate(request.metadata())?;
+    let value = MetadataValue::from_str(&user)
+        .map_err(|_| unauthenticated(format!("invalid user name '{}'", user)))?;
+    request.metadata_mut().insert(USER_KEY, value);
+    Ok(request)
+}
+
+/// Discard any user supplied by the client, for servers which do not
+/// authenticate requests. Installed as an interceptor, it prevents clients
+/// from naming themselves the author of their revisions.
+pub fn discard_user(mut request: Request<()>) -> Result<Request<()>, Status> {
+    request.metadata_mut().remove(USER_KEY);
+    Ok(request)
+}
+
+/// Retrieve the authenticated user recorded in a request's metadata, if any
+pub fn user<T>(request: &Request<T>) -> Option<String> {
+    request
+        .metadata()
+        .get(USER_KEY)
+        .and_then(|value| value.to_str().ok())
+        .map(|user| user.to_string())
+}
+
+fn unauthenticated<S: Into<String>>(message: S) -> Status {
+    Status::new(Code::Unauthenticated, message)
+}
+
+fn read_toml<T>(path: &Path) -> Result<T, AuthError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let contents = fs::read_to_string(path).context(ReadFile { path })?;
+    toml::from_str(&contents).context(ParseFile { path })
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TokensFile {
+    // user -> token
+    tokens: HashMap<String, String>,
+}
+
+/// Authenticates requests bearing one of a fixed set of tokens, supplied in
+/// the `authorization` metadata as `Bearer <token>`.
+#[derive(Debug, Clone, Default)]
+pub struct StaticTokens {
+    // token -> user
+    users: HashMap<String, String>,
+}
+
+impl StaticTokens {
+    /// New up an empty StaticTokens, which authenticates no one
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Given a mutable instance of Self, a user, and a token, authenticate
+    /// requests bearing the token as the user.
+    pub fn token<U, T>(mut self, user: U, token: T) -> Self
+    where
+        U: Into<String>,
+        T: Into<String>,
+    {
+        self.users.insert(token.into(), user.into());
+        self
+    }
+
+    /// Read the tokens from a TOML file mapping users to their tokens
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the tokens file
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - StaticTokens
+    /// - Err - AuthError
+    pub fn from_file(path: &Path) -> Result<Self, AuthError> {
+        let TokensFile { tokens } = read_toml(path)?;
+        Ok(tokens
+            .into_iter()
+            .fold(Self::new(), |tokens, (user, token)| {
+                tokens.token(user, token)
+            }))
+    }
+}
+
+impl Authenticator for StaticTokens {
+    fn authenticate(&self, metadata: &MetadataMap) -> Result<String, Status> {
+        let header = metadata
+            .get(AUTHORIZATION_KEY)
+            .ok_or_else(|| unauthenticated("missing bearer token"))?
+            .to_str()
+            .map_err(|_| unauthenticated("invalid authorization metadata"))?;
+        const BEARER: &str = "Bearer ";
+        if !header.starts_with(BEARER) {
+            return Err(unauthenticated("expected a bearer token"));
+        }
+        let token = &header[BEARER.len()..];
+        self.users
+            .get(token.trim())
+            .cloned()
+            .ok_or_else(|| unauthenticated("invalid bearer token"))
+    }
+}
+
+/// The extent of a mutation, against which a user's permissions are checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope<'a> {
+    /// Mutations which are not confined to a level, such as adding a package
+    Global,
+    /// Mutations confined to a level
+    Level(&'a str),
+}
+
+/// Write permissions, granted per level
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Policy {
+    /// Users who may make any mutation
+    pub admins: Vec<String>,
+    /// The users who may write at each level, and the levels beneath it
+    pub levels: HashMap<String, Vec<String>>,
+}
+
+impl Policy {
+    /// Read a Policy from a TOML file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the policy file
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - Policy
+    /// - Err - AuthError
+    pub fn from_file(path: &Path) -> Result<Self, AuthError> {
+        read_toml(path)
+    }
+
+    /// Parse a Policy from a string in TOML format
+    pub fn from_toml_str(contents: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    /// Whether the user may make a mutation of the supplied scope
+    pub fn allows(&self, user: &str, scope: Scope<'_>) -> bool {
+        if self.admins.iter().any(|admin| admin == user) {
+            return true;
+        }
+        match scope {
+            Scope::Global => false,
+            Scope::Level(level) => self.levels.iter().any(|(granted, users)| {
+                covers(granted, level) && users.iter().any(|u| u == user || u == ANY_USER)
+            }),
+        }
+    }
+}
+
+// whether a grant on one level extends to another
+fn covers(granted: &str, level: &str) -> bool {
+    level == granted || (level.starts_with(granted) && level[granted.len()..].starts_with('.'))
+}
+
+/// The user on whose behalf a request is made, along with the policy
+/// governing what they may change.
+#[derive(Debug, Clone, Default)]
+pub struct Caller {
+    user: Option<String>,
+    policy: Option<Arc<Policy>>,
+}
+
+impl Caller {
+    /// New up a Caller
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The authenticated user, if authentication is enabled
+    /// * `policy` - The policy, if authorization is enabled
+    ///
+    /// # Returns
+    ///
+    /// * Caller
+    pub fn new(user: Option<String>, policy: Option<Arc<Policy>>) -> Self {
+        Self { user, policy }
+    }
+
+    /// The authenticated user, if any
+    pub fn user(&self) -> Option<&str> {
+        self.user.as_deref()
+    }
+
+    /// The author to record in the revision. The authenticated user takes
+    /// precedence over the author supplied in the request.
+    pub fn author(&self, supplied: String) -> String {
+        match self.user {
+            Some(ref user) => user.clone(),
+            None => supplied,
+        }
+    }
+
+    /// Verify that the caller may make a mutation of the supplied scope,
+    /// returning a PermissionDenied Status if they may not.
+    pub fn authorize(&self, scope: Scope<'_>) -> Result<(), Status> {
+        let policy = match self.policy {
+            Some(ref policy) => policy,
+            None => return Ok(()),
+        };
+        let user = self
+            .user()
+            .ok_or_else(|| unauthenticated("writes require an authenticated user"))?;
+        if policy.allows(user, scope) {
+            return Ok(());
+        }
+        let message = match scope {
+            Scope::Global => format!("{} may not make global changes", user),
+            Scope::Level(level) => format!("{} may not write to level {}", user, level),
+        };
+        Err(Status::new(Code::PermissionDenied, message))
+    }
+
+    /// Verify that the caller may write to each of the supplied levels
+    pub fn authorize_levels<I, S>(&self, levels: I) -> Result<(), Status>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for level in levels {
+            self.authorize(Scope::Level(level.as_ref()))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(token: Option<&str>, user: Option<&str>) -> Request<()> {
+        let mut request = Request::new(());
+        if let Some(token) = token {
+            let value = MetadataValue::from_str(&format!("Bearer {}", token)).unwrap();
+            request.metadata_mut().insert(AUTHORIZATION_KEY, value);
+        }
+        if let Some(user) = user {
+            let value = MetadataValue::from_str(user).unwrap();
+            request.metadata_mut().insert(USER_KEY, value);
+        }
+        request
+    }
+
+    #[test]
+    fn can_authenticate_bearer_token() {
+        let tokens = StaticTokens::new().token("jgerber", "s3cr3t");
+        let request = authenticate_request(&tokens, request(Some("s3cr3t"), None)).unwrap();
+        assert_eq!(user(&request), Some("jgerber".to_string()));
+    }
+
+    #[test]
+    fn cannot_authenticate_unknown_token() {
+        let tokens = StaticTokens::new().token("jgerber", "s3cr3t");
+        let err = authenticate_request(&tokens, request(Some("guess"), None)).unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+        let err = authenticate_request(&tokens, request(None, None)).unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+    }
+
+    #[test]
+    fn replaces_user_supplied_by_client() {
+        let tokens = StaticTokens::new().token("alice", "s3cr3t");
+        let request =
+            authenticate_request(&tokens, request(Some("s3cr3t"), Some("jgerber"))).unwrap();
+        assert_eq!(user(&request), Some("alice".to_string()));
+    }
+
+    #[test]
+    fn can_grant_levels_beneath_a_show() {
+        let policy = Policy::from_toml_str(
+            r#"
+            admins = ["jgerber"]
+            [levels]
+            dev01 = ["alice"]
+            sandbox = ["*"]
+            "#,
+        )
+        .unwrap();
+        assert!(policy.allows("jgerber", Scope::Global));
+        assert!(!policy.allows("alice", Scope::Global));
+        assert!(policy.allows("alice", Scope::Level("dev01")));
+        assert!(policy.allows("alice", Scope::Level("dev01.rd.0001")));
+        assert!(!policy.allows("alice", Scope::Level("dev011")));
+        assert!(!policy.allows("alice", Scope::Level("facility")));
+        assert!(policy.allows("bob", Scope::Level("sandbox.rd")));
+    }
+
+    #[test]
+    fn caller_is_authorized_by_policy() {
+        let policy = Arc::new(Policy::from_toml_str("[levels]\ndev01 = [\"alice\"]\n").unwrap());
+        let caller = Caller::new(Some("alice".to_string()), Some(policy.clone()));
+        assert!(caller.authorize_levels(&["dev01", "dev01.rd"]).is_ok());
+        let err = caller.authorize(Scope::Level("dev02")).unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+        let err = Caller::new(None, Some(policy))
+            .authorize(Scope::Global)
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+        assert!(Caller::default().authorize(Scope::Global).is_ok());
+    }
+
+    #[test]
+    fn authenticated_user_is_the_author() {
+        let caller = Caller::new(Some("alice".to_string()), None);
+        assert_eq!(caller.author("jgerber".to_string()), "alice");
+        assert_eq!(Caller::default().author("jgerber".to_string()), "jgerber");
+    }
+}
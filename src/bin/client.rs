@@ -1,18 +1,38 @@
 use packybara_grpc::client as pbclient;
+use packybara_grpc::health::ServingStatus;
 mod client_cli;
 use client_cli::*;
-use packybara_grpc::url_builder;
+use packybara_grpc::url::GrpcUrl;
+use packybara_grpc::{url_builder, Coords};
+use std::fs::File;
+use std::io::BufWriter;
 use structopt::StructOpt;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let opt = Pb::from_args();
-    let url = url_builder::UrlBuilder::new()
-        .host(url_builder::Host::Localhost)
-        .port(50051)
-        .build(); //"http://[::1]:50051"
-    let mut client = pbclient::Client::new(url).await?;
-    let Pb { crud, .. } = opt;
+    let Pb {
+        url,
+        ca,
+        cert,
+        key,
+        domain,
+        token,
+        crud,
+        ..
+    } = opt;
+    let url = match url {
+        Some(url) => GrpcUrl::parse(&url)?,
+        None => url_builder::UrlBuilder::new()
+            .host(url_builder::Host::Localhost)
+            .port(50051)
+            .build(), //"http://[::1]:50051"
+    };
+    let mut tls = pbclient::tls::Options::new().ca_opt(ca).domain_opt(domain);
+    if let (Some(cert), Some(key)) = (cert, key) {
+        tls = tls.identity(cert, key);
+    }
+    let mut client = pbclient::Client::connect(url, tls, token).await?;
     match crud {
         PbCrud::Find { cmd } => match cmd {
             PbFind::VersionPin {
@@ -61,87 +81,456 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                             .isolate_facility_opt(Some(isolate_facility))
                             .search_mode_opt(search_mode)
                             .order_direction_opt(order_direction)
+                            .order_by_opt(order_by)
+                            .full_withs_opt(Some(full_withs))
+                            .limit_opt(limit),
+                    )
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbFind::Roles {
+                role,
+                category,
+                order_by,
+            } => {
+                let response = client
+                    .get_roles(
+                        pbclient::get_roles::Options::new()
+                            .role_opt(role)
+                            .category_opt(category)
+                            .order_by_opt(order_by),
+                    )
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbFind::Platforms { platform, order_by } => {
+                let response = client
+                    .get_platforms(
+                        pbclient::get_platforms::Options::new()
+                            .platform_opt(platform)
+                            .order_by_opt(order_by),
+                    )
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbFind::Sites { site } => {
+                let response = client
+                    .get_sites(pbclient::get_sites::Options::new().site_opt(site))
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbFind::Levels {
+                level,
+                show,
+                depth,
+                order_by,
+            } => {
+                let response = client
+                    .get_levels(
+                        pbclient::get_levels::Options::new()
+                            .level_opt(level)
+                            .show_opt(show)
+                            .depth_opt(depth)
+                            .order_by_opt(order_by),
+                    )
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbFind::Packages {} => {
+                let response = client.get_packages().await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbFind::Distributions {
+                package,
+                version,
+                order_direction,
+            } => {
+                let response = client
+                    .get_distributions(
+                        pbclient::get_distributions::Options::new()
+                            .package_opt(package)
+                            .version_opt(version)
+                            .order_direction_opt(order_direction),
+                    )
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbFind::PkgCoords {
+                package,
+                level,
+                role,
+                platform,
+                site,
+                search_mode,
+                order_by,
+            } => {
+                let response = client
+                    .get_pkgcoords(
+                        pbclient::get_pkgcoords::Options::new()
+                            .package_opt(package)
+                            .level_opt(level)
+                            .role_opt(role)
+                            .platform_opt(platform)
+                            .site_opt(site)
+                            .search_mode_opt(search_mode)
                             .order_by_opt(order_by),
                     )
                     .await?;
                 println!("RESPONSE={:#?}", response);
             }
-            _ => println!("Not Implemented"),
-            // PbFind::Roles { .. } => {
-            //     cmd::all_roles::find(client, cmd).await?;
-            // }
-            // PbFind::Platforms { .. } => {
-            //     cmd::all_platforms::find(client, cmd).await?;
-            // }
-            // PbFind::Sites { .. } => {
-            //     cmd::all_sites::find(client, cmd).await?;
-            // }
-            // PbFind::Levels { .. } => {
-            //     cmd::all_levels::find(client, cmd).await?;
-            // }
-            // PbFind::Pins { .. } => {
-            //     cmd::pins::find(client, cmd).await?;
-            // }
-            // PbFind::VersionPinWiths { .. } => {
-            //     cmd::versionpin_withs::find(client, cmd).await?;
-            // }
-            // PbFind::Withs { .. } => {
-            //     cmd::withs::find(client, cmd).await?;
-            // }
-            // PbFind::Packages { .. } => {
-            //     cmd::all_packages::find(client, cmd).await?;
-            // }
-            // PbFind::Distributions { .. } => {
-            //     cmd::all_distributions::find(client, cmd).await?;
-            // }
-            // PbFind::PkgCoords { .. } => {
-            //     cmd::pkgcoords::find(client, cmd).await?;
-            // }
-            // PbFind::Revisions { .. } => {
-            //     cmd::all_revisions::find(client, cmd).await?;
-            // }
-            // PbFind::Changes { .. } => {
-            //     cmd::all_changes::find(client, cmd).await?;
-            // }
+            PbFind::Pins {
+                level,
+                role,
+                platform,
+                site,
+                search_mode,
+                limit,
+                order_by,
+            } => {
+                let response = client
+                    .get_pins(
+                        pbclient::get_pins::Options::new()
+                            .level_opt(level)
+                            .role_opt(role)
+                            .platform_opt(platform)
+                            .site_opt(site)
+                            .search_mode_opt(search_mode)
+                            .order_by_opt(order_by)
+                            .limit_opt(limit),
+                    )
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbFind::Withs {
+                package,
+                level,
+                role,
+                platform,
+                site,
+                search_mode,
+                limit,
+                order_by,
+            } => {
+                if search_mode.is_some() || limit.is_some() || order_by.is_some() {
+                    return Err(
+                        "find withs does not support --search, --limit or --order-by".into(),
+                    );
+                }
+                let response = client
+                    .get_withs(
+                        pbclient::get_withs::Options::new(package)
+                            .level_opt(level)
+                            .role_opt(role)
+                            .platform_opt(platform)
+                            .site_opt(site),
+                    )
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbFind::VersionPinWiths { versionpin_id } => {
+                let response = client.get_version_pin_withs(versionpin_id).await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbFind::Revisions {
+                id,
+                transaction_id,
+                author,
+                order_by,
+                order_direction,
+                limit,
+            } => {
+                let response = client
+                    .get_revisions(
+                        pbclient::get_revisions::Options::new()
+                            .id_opt(id)
+                            .transaction_id_opt(transaction_id)
+                            .author_opt(author)
+                            .order_by_opt(order_by)
+                            .order_direction_opt(order_direction)
+                            .limit_opt(limit),
+                    )
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbFind::Changes { transaction_id } => {
+                let response = client.get_changes(transaction_id).await?;
+                println!("RESPONSE={:#?}", response);
+            }
+        },
+        PbCrud::Add { cmd } => match cmd {
+            PbAdd::Packages {
+                comment,
+                author,
+                names,
+            } => {
+                let response = client
+                    .add_packages(pbclient::add::Options::new(
+                        names,
+                        author.unwrap_or_else(whoami::username),
+                        comment,
+                    ))
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbAdd::Levels {
+                comment,
+                author,
+                names,
+            } => {
+                let response = client
+                    .add_levels(pbclient::add::Options::new(
+                        names,
+                        author.unwrap_or_else(whoami::username),
+                        comment,
+                    ))
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbAdd::Roles {
+                comment,
+                author,
+                names,
+            } => {
+                let response = client
+                    .add_roles(pbclient::add::Options::new(
+                        names,
+                        author.unwrap_or_else(whoami::username),
+                        comment,
+                    ))
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbAdd::Platforms {
+                comment,
+                author,
+                names,
+            } => {
+                let response = client
+                    .add_platforms(pbclient::add::Options::new(
+                        names,
+                        author.unwrap_or_else(whoami::username),
+                        comment,
+                    ))
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbAdd::VersionPins {
+                comment,
+                author,
+                package,
+                version,
+                levels,
+                roles,
+                platforms,
+                sites,
+            } => {
+                let response = client
+                    .add_version_pins(
+                        pbclient::add_versionpins::Options::new(
+                            package,
+                            version,
+                            author.unwrap_or_else(whoami::username),
+                            comment,
+                        )
+                        .levels(levels)
+                        .roles(roles)
+                        .platforms(platforms)
+                        .sites(sites),
+                    )
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbAdd::Withs {
+                comment,
+                author,
+                versionpin_id,
+                withs,
+            } => {
+                let response = client
+                    .add_withs(pbclient::withs::Options::new(
+                        versionpin_id,
+                        withs,
+                        author.unwrap_or_else(whoami::username),
+                        comment,
+                    ))
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
         },
-        // PbCrud::Add { cmd } => match cmd {
-        //     PbAdd::Packages { .. } => {
-        //         let tx = client.transaction().await?;
-        //         cmd::all_packages::add(tx, cmd).await?;
-        //     }
-        //     PbAdd::Levels { .. } => {
-        //         let tx = client.transaction().await?;
-        //         cmd::all_levels::add(tx, cmd).await?;
-        //     }
-        //     PbAdd::Roles { .. } => {
-        //         let tx = client.transaction().await?;
-        //         cmd::all_roles::add(tx, cmd).await?;
-        //     }
-        //     PbAdd::Platforms { .. } => {
-        //         let tx = client.transaction().await?;
-        //         cmd::all_platforms::add(tx, cmd).await?;
-        //     }
-        //     PbAdd::Withs { .. } => {
-        //         let tx = client.transaction().await?;
-        //         cmd::withs::add(tx, cmd).await?;
-        //     }
-        //     PbAdd::VersionPins { .. } => {
-        //         let tx = client.transaction().await?;
-        //         cmd::versionpins::add(tx, cmd).await?;
-        //     }
-        // },
-        // PbCrud::Set { cmd } => match cmd {
-        //     PbSet::VersionPins { .. } => {
-        //         let tx = client.transaction().await?;
-        //         cmd::versionpins::set(tx, cmd).await?;
-        //     }
-        // },
-        // PbCrud::Export { cmd } => match cmd {
-        //     PbExport::PackagesXml { .. } => {
-        //         cmd::export::export(client, cmd).await?;
-        //     }
-        // },
-        //_ => println!("Not implemented"),
+        PbCrud::Set { cmd } => match cmd {
+            PbSet::VersionPins {
+                comment,
+                author,
+                vpin_ids,
+                dist_ids,
+                package,
+                version,
+                level,
+                role,
+                platform,
+                site,
+            } => {
+                if vpin_ids.len() != dist_ids.len() {
+                    return Err("each versionpin id must be paired with a distribution id".into());
+                }
+                let mut options = pbclient::set_versionpins::Options::new(
+                    author.unwrap_or_else(whoami::username),
+                    comment,
+                );
+                for (vpin_id, dist_id) in vpin_ids.into_iter().zip(dist_ids) {
+                    options = options.change_by_id(vpin_id, dist_id);
+                }
+                if let (Some(package), Some(version)) = (package, version) {
+                    let coords = Coords {
+                        level: level.unwrap_or_else(|| "facility".to_string()),
+                        role: role.unwrap_or_else(|| "any".to_string()),
+                        platform: platform.unwrap_or_else(|| "any".to_string()),
+                        site: site.unwrap_or_else(|| "any".to_string()),
+                    };
+                    options = options.change_by_coords(package, coords, version);
+                }
+                let response = client.set_version_pins(options).await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbSet::Withs {
+                comment,
+                author,
+                versionpin_id,
+                withs,
+            } => {
+                let response = client
+                    .reorder_withs(pbclient::withs::Options::new(
+                        versionpin_id,
+                        withs,
+                        author.unwrap_or_else(whoami::username),
+                        comment,
+                    ))
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+        },
+        PbCrud::Delete { cmd } => match cmd {
+            PbDelete::Withs {
+                comment,
+                author,
+                versionpin_id,
+                withs,
+            } => {
+                let response = client
+                    .remove_withs(pbclient::withs::Options::new(
+                        versionpin_id,
+                        withs,
+                        author.unwrap_or_else(whoami::username),
+                        comment,
+                    ))
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbDelete::VersionPins {
+                comment,
+                author,
+                dry_run,
+                ids,
+            } => {
+                let response = client
+                    .delete_version_pins(
+                        pbclient::delete::Options::new(
+                            ids,
+                            author.unwrap_or_else(whoami::username),
+                            comment,
+                        )
+                        .dry_run(dry_run),
+                    )
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbDelete::VersionPinWiths {
+                comment,
+                author,
+                dry_run,
+                versionpin_id,
+                ids,
+            } => {
+                let response = client
+                    .delete_withs(
+                        versionpin_id,
+                        pbclient::delete::Options::new(
+                            ids,
+                            author.unwrap_or_else(whoami::username),
+                            comment,
+                        )
+                        .dry_run(dry_run),
+                    )
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbDelete::PkgCoords {
+                comment,
+                author,
+                dry_run,
+                ids,
+            } => {
+                let response = client
+                    .delete_pkgcoords(
+                        pbclient::delete::Options::new(
+                            ids,
+                            author.unwrap_or_else(whoami::username),
+                            comment,
+                        )
+                        .dry_run(dry_run),
+                    )
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+            PbDelete::Distributions {
+                comment,
+                author,
+                dry_run,
+                ids,
+            } => {
+                let response = client
+                    .delete_distributions(
+                        pbclient::delete::Options::new(
+                            ids,
+                            author.unwrap_or_else(whoami::username),
+                            comment,
+                        )
+                        .dry_run(dry_run),
+                    )
+                    .await?;
+                println!("RESPONSE={:#?}", response);
+            }
+        },
+        PbCrud::Export { cmd } => match cmd {
+            PbExport::PackagesXml {
+                show,
+                level,
+                role,
+                platform,
+                site,
+                output,
+            } => {
+                let mut file = BufWriter::new(File::create(&output)?);
+                let written = client
+                    .export_packages_xml(
+                        pbclient::export_packages_xml::Options::new(show)
+                            .level_opt(level)
+                            .role_opt(role)
+                            .platform_opt(platform)
+                            .site_opt(site),
+                        &mut file,
+                    )
+                    .await?;
+                println!("Wrote {} bytes to {}", written, output.display());
+            }
+        },
+        PbCrud::Health {} => {
+            let status = client.check_health().await?;
+            println!("{:?}", status);
+            if status != ServingStatus::Serving {
+                std::process::exit(1);
+            }
+        }
     }
 
     Ok(())
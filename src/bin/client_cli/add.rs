@@ -0,0 +1,103 @@
+use packybara::types::IdType;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug, PartialEq)]
+#[structopt(about = "Add entities to db")]
+pub enum PbAdd {
+    /// Add one or more packages to the database.
+    #[structopt(display_order = 1)]
+    Packages {
+        /// A description of the change.
+        #[structopt(short, long, display_order = 1)]
+        comment: String,
+        /// The author of the change. Defaults to the current user.
+        #[structopt(short, long, display_order = 2)]
+        author: Option<String>,
+        /// The names of the packages to add.
+        #[structopt(name = "PACKAGE", required = true)]
+        names: Vec<String>,
+    },
+    /// Add one or more levels (ie show[.seq[.shot]]) to the database.
+    #[structopt(display_order = 2)]
+    Levels {
+        /// A description of the change.
+        #[structopt(short, long, display_order = 1)]
+        comment: String,
+        /// The author of the change. Defaults to the current user.
+        #[structopt(short, long, display_order = 2)]
+        author: Option<String>,
+        /// The names of the levels to add.
+        #[structopt(name = "LEVEL", required = true)]
+        names: Vec<String>,
+    },
+    /// Add one or more roles to the database.
+    #[structopt(display_order = 3)]
+    Roles {
+        /// A description of the change.
+        #[structopt(short, long, display_order = 1)]
+        comment: String,
+        /// The author of the change. Defaults to the current user.
+        #[structopt(short, long, display_order = 2)]
+        author: Option<String>,
+        /// The names of the roles to add.
+        #[structopt(name = "ROLE", required = true)]
+        names: Vec<String>,
+    },
+    /// Add one or more platforms to the database.
+    #[structopt(display_order = 4)]
+    Platforms {
+        /// A description of the change.
+        #[structopt(short, long, display_order = 1)]
+        comment: String,
+        /// The author of the change. Defaults to the current user.
+        #[structopt(short, long, display_order = 2)]
+        author: Option<String>,
+        /// The names of the platforms to add.
+        #[structopt(name = "PLATFORM", required = true)]
+        names: Vec<String>,
+    },
+    /// Pin a distribution at every combination of the supplied coords.
+    #[structopt(display_order = 5)]
+    VersionPins {
+        /// A description of the change.
+        #[structopt(short, long, display_order = 1)]
+        comment: String,
+        /// The author of the change. Defaults to the current user.
+        #[structopt(short, long, display_order = 2)]
+        author: Option<String>,
+        /// The name of the package to pin.
+        #[structopt(name = "PACKAGE")]
+        package: String,
+        /// The version of the package to pin.
+        #[structopt(name = "VERSION")]
+        version: String,
+        /// One or more levels to pin at. Defaults to 'facility'.
+        #[structopt(short = "L", long, display_order = 3)]
+        levels: Vec<String>,
+        /// One or more roles to pin at. Defaults to 'any'.
+        #[structopt(short = "R", long, display_order = 4)]
+        roles: Vec<String>,
+        /// One or more platforms to pin at. Defaults to 'any'.
+        #[structopt(short = "P", long, display_order = 5)]
+        platforms: Vec<String>,
+        /// One or more sites to pin at. Defaults to 'any'.
+        #[structopt(short = "S", long, display_order = 6)]
+        sites: Vec<String>,
+    },
+    /// Append one or more withs to those of a versionpin.
+    #[structopt(display_order = 6)]
+    Withs {
+        /// A description of the change.
+        #[structopt(short, long, display_order = 1)]
+        comment: String,
+        /// The author of the change. Defaults to the current user.
+        #[structopt(short, long, display_order = 2)]
+        author: Option<String>,
+        /// The id of the versionpin.
+        #[structopt(name = "VERSIONPIN_ID")]
+        versionpin_id: IdType,
+        /// The names of the with packages to append.
+        #[structopt(name = "WITH", required = true)]
+        withs: Vec<String>,
+    },
+}
@@ -0,0 +1,90 @@
+use packybara::types::IdType;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug, PartialEq)]
+#[structopt(about = "Remove entities from db")]
+pub enum PbDelete {
+    /// Remove one or more withs from a versionpin.
+    #[structopt(display_order = 1)]
+    Withs {
+        /// A description of the change.
+        #[structopt(short, long, display_order = 1)]
+        comment: String,
+        /// The author of the change. Defaults to the current user.
+        #[structopt(short, long, display_order = 2)]
+        author: Option<String>,
+        /// The id of the versionpin.
+        #[structopt(name = "VERSIONPIN_ID")]
+        versionpin_id: IdType,
+        /// The names of the with packages to remove.
+        #[structopt(name = "WITH", required = true)]
+        withs: Vec<String>,
+    },
+    /// Delete one or more versionpins, by id. Versionpins with withs are not deleted.
+    #[structopt(display_order = 2)]
+    VersionPins {
+        /// A description of the change.
+        #[structopt(short, long, display_order = 1)]
+        comment: String,
+        /// The author of the change. Defaults to the current user.
+        #[structopt(short, long, display_order = 2)]
+        author: Option<String>,
+        /// Report the rows which would be deleted, without deleting them.
+        #[structopt(long = "dry-run", display_order = 3)]
+        dry_run: bool,
+        /// The ids of the versionpins to delete.
+        #[structopt(name = "ID", required = true)]
+        ids: Vec<IdType>,
+    },
+    /// Delete one or more withs of a versionpin, by id.
+    #[structopt(display_order = 3)]
+    VersionPinWiths {
+        /// A description of the change.
+        #[structopt(short, long, display_order = 1)]
+        comment: String,
+        /// The author of the change. Defaults to the current user.
+        #[structopt(short, long, display_order = 2)]
+        author: Option<String>,
+        /// Report the rows which would be deleted, without deleting them.
+        #[structopt(long = "dry-run", display_order = 3)]
+        dry_run: bool,
+        /// The id of the versionpin.
+        #[structopt(name = "VERSIONPIN_ID")]
+        versionpin_id: IdType,
+        /// The ids of the withs to delete.
+        #[structopt(name = "ID", required = true)]
+        ids: Vec<IdType>,
+    },
+    /// Delete one or more pkgcoords, by id. Pkgcoords referenced by versionpins are not deleted.
+    #[structopt(display_order = 4)]
+    PkgCoords {
+        /// A description of the change.
+        #[structopt(short, long, display_order = 1)]
+        comment: String,
+        /// The author of the change. Defaults to the current user.
+        #[structopt(short, long, display_order = 2)]
+        author: Option<String>,
+        /// Report the rows which would be deleted, without deleting them.
+        #[structopt(long = "dry-run", display_order = 3)]
+        dry_run: bool,
+        /// The ids of the pkgcoords to delete.
+        #[structopt(name = "ID", required = true)]
+        ids: Vec<IdType>,
+    },
+    /// Delete one or more distributions, by id. Distributions referenced by versionpins are not deleted.
+    #[structopt(display_order = 5)]
+    Distributions {
+        /// A description of the change.
+        #[structopt(short, long, display_order = 1)]
+        comment: String,
+        /// The author of the change. Defaults to the current user.
+        #[structopt(short, long, display_order = 2)]
+        author: Option<String>,
+        /// Report the rows which would be deleted, without deleting them.
+        #[structopt(long = "dry-run", display_order = 3)]
+        dry_run: bool,
+        /// The ids of the distributions to delete.
+        #[structopt(name = "ID", required = true)]
+        ids: Vec<IdType>,
+    },
+}
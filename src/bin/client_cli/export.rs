@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug, PartialEq)]
+#[structopt(about = "Serialize state")]
+pub enum PbExport {
+    /// Export the versionpins of a show as packages.xml.
+    #[structopt(display_order = 1)]
+    PackagesXml {
+        /// The name of the show to export.
+        #[structopt(name = "SHOW")]
+        show: String,
+        /// The level (ie show[.seq[.shot]]) to export from. Defaults to the show.
+        #[structopt(short = "L", long, display_order = 1)]
+        level: Option<String>,
+        /// The role (eg model or anim_beta). Defaults to 'any'.
+        #[structopt(short = "R", long, display_order = 2)]
+        role: Option<String>,
+        /// The operating system name (eg cent7_64). Defaults to 'any'.
+        #[structopt(short = "P", long, display_order = 3)]
+        platform: Option<String>,
+        /// The location name (eg portland). Defaults to 'any'.
+        #[structopt(short = "S", long, display_order = 4)]
+        site: Option<String>,
+        /// The file to write.
+        #[structopt(short, long, parse(from_os_str), display_order = 5)]
+        output: PathBuf,
+    },
+}
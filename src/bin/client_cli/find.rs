@@ -1,4 +1,5 @@
 use packybara::types::{IdType, LongIdType};
+use packybara_grpc::{LtreeSearchMode, OrderDirection, SearchAttribute};
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug, PartialEq)]
@@ -62,17 +63,17 @@ pub enum PbFind {
         site: Option<String>,
         /// The search mode - ancestor (or down), exact, descendant (or up).
         #[structopt(short, long = "search", display_order = 7)]
-        search_mode: Option<String>,
+        search_mode: Option<LtreeSearchMode>,
         /// Limit the number of returned items.
         #[structopt(short, long, display_order = 8)]
-        limit: Option<IdType>,
+        limit: Option<u32>,
         /// Provide one or more comma separated items to order the return by.
-        #[structopt(short, long = "order-by", display_order = 9)]
-        order_by: Option<String>,
+        #[structopt(short, long = "order-by", use_delimiter = true, display_order = 9)]
+        order_by: Option<Vec<SearchAttribute>>,
         /// Provide direction to search in
         #[structopt(short = "D", long = "order-direction", display_order = 10)]
-        order_direction: Option<String>,
-        /// Do not truncate the withs if true. Defaults to false.
+        order_direction: Option<OrderDirection>,
+        /// Return the distributions the withs resolve to, rather than their names.
         #[structopt(short = "w", long = "withs", display_order = 11)]
         full_withs: bool,
         /// When searching "up", if the level is facility, treat the
@@ -109,12 +110,14 @@ pub enum PbFind {
         #[structopt(short = "S", long, display_order = 4)]
         site: Option<String>,
         /// Search mode - ancestor (or down), exact, descendant (or up).
+        /// Not supported by withs, which are resolved at the pin's coords.
         #[structopt(short, long = "search", display_order = 5)]
         search_mode: Option<String>,
-        /// Limit the number of returned items.
+        /// Limit the number of returned items. Not supported by withs.
         #[structopt(short, long, display_order = 6)]
         limit: Option<IdType>,
         /// Provide one or more comma separated items to order the return by.
+        /// Not supported by withs, which are returned in their pinned order.
         #[structopt(short, long = "order-by", display_order = 7)]
         order_by: Option<String>,
     },
@@ -135,13 +138,13 @@ pub enum PbFind {
         site: Option<String>,
         /// Search mode - ancestor (or down), exact, descendant (or up). Defaults to 'ancestor'.
         #[structopt(short, long = "search", display_order = 5)]
-        search_mode: Option<String>,
+        search_mode: Option<LtreeSearchMode>,
         /// Limit the number of returned items.
         #[structopt(short, long, display_order = 6)]
         limit: Option<IdType>,
         /// Provide one or more comma separated items to order the return by.
-        #[structopt(short, long = "order-by", display_order = 7)]
-        order_by: Option<String>,
+        #[structopt(short, long = "order-by", use_delimiter = true, display_order = 7)]
+        order_by: Option<Vec<SearchAttribute>>,
     },
     #[structopt(display_order = 6)]
     /// Get a simple list of all roles.
@@ -207,7 +210,7 @@ pub enum PbFind {
         // order_by: Option<String>,
         /// The order direction. may be "asc" or "desc".
         #[structopt(short = "D", long = "order-direction", display_order = 3)]
-        order_direction: Option<String>,
+        order_direction: Option<OrderDirection>,
     },
     #[structopt(display_order = 12)]
     /// Search for package coordinates. Package coordinates are simply
@@ -232,7 +235,7 @@ pub enum PbFind {
         /// descendant (or 'up','u', '>').
         /// Defaults to 'ancestor'.
         #[structopt(short, long = "search", display_order = 6)]
-        search_mode: Option<String>,
+        search_mode: Option<LtreeSearchMode>,
         // /// Limit the number of returned items.
         // #[structopt(short, long, display_order = 6)]
         // limit: Option<IdType>,
@@ -258,7 +261,7 @@ pub enum PbFind {
         order_by: Option<String>,
         /// The order direction. may be "asc" or "desc".
         #[structopt(short = "D", long = "order-direction", display_order = 5)]
-        order_direction: Option<String>,
+        order_direction: Option<OrderDirection>,
         // Limit the number of returned items.
         #[structopt(short, long, display_order = 6)]
         limit: Option<IdType>,
@@ -1,12 +1,15 @@
 pub(crate) mod find;
 pub(crate) use find::PbFind;
-// pub mod add;
-// pub use add::*;
-// pub mod set;
-// pub use set::*;
-// pub mod export;
-// pub use export::*;
+pub(crate) mod add;
+pub(crate) use add::PbAdd;
+pub(crate) mod set;
+pub(crate) use set::PbSet;
+pub(crate) mod delete;
+pub(crate) use delete::PbDelete;
+pub(crate) mod export;
+pub(crate) use export::PbExport;
 
+use std::path::PathBuf;
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug, PartialEq)]
@@ -16,6 +19,27 @@ pub struct Pb {
     /// (levels: trace, debug, info, warn, error)
     #[structopt(long)]
     pub loglevel: Option<String>,
+    /// The url of the server. Defaults to http://localhost:50051.
+    #[structopt(long)]
+    pub url: Option<String>,
+    /// Path to a PEM encoded CA bundle used to verify the server's
+    /// certificate, when the url is https.
+    #[structopt(long, parse(from_os_str))]
+    pub ca: Option<PathBuf>,
+    /// Path to the PEM encoded client certificate presented to servers
+    /// which require one. Requires --key.
+    #[structopt(long, parse(from_os_str), requires = "key")]
+    pub cert: Option<PathBuf>,
+    /// Path to the PEM encoded private key of the client certificate.
+    #[structopt(long, parse(from_os_str), requires = "cert")]
+    pub key: Option<PathBuf>,
+    /// Verify the server's certificate against this domain name, rather
+    /// than the host of the url.
+    #[structopt(long)]
+    pub domain: Option<String>,
+    /// The bearer token presented to servers which authenticate requests.
+    #[structopt(long, env = "PACKYBARA_GRPC_TOKEN", hide_env_values = true)]
+    pub token: Option<String>,
     /// Subcommand
     #[structopt(subcommand)] // Note that we mark a field as a subcommand
     pub crud: PbCrud,
@@ -31,27 +55,35 @@ pub enum PbCrud {
         #[structopt(subcommand)]
         cmd: PbFind,
     },
-    // /// Update things in the database.
-    // #[structopt(display_order = 2)]
-    // Set {
-    //     /// Read subcommands
-    //     #[structopt(subcommand)]
-    //     cmd: PbSet,
-    // },
-    // /// Create new things in the database.
-    // #[structopt(display_order = 3)]
-    // Add {
-    //     /// Read subcommands
-    //     #[structopt(subcommand)]
-    //     cmd: PbAdd,
-    // },
-    // /// Remove things from the database.
-    // #[structopt(display_order = 4)]
-    // Delete {},
-    // /// Serialize state.
-    // #[structopt(display_order = 5)]
-    // Export {
-    //     #[structopt(subcommand)]
-    //     cmd: PbExport,
-    // },
+    /// Update things in the database.
+    #[structopt(display_order = 2)]
+    Set {
+        /// Update subcommands
+        #[structopt(subcommand)]
+        cmd: PbSet,
+    },
+    /// Create new things in the database.
+    #[structopt(display_order = 3)]
+    Add {
+        /// Create subcommands
+        #[structopt(subcommand)]
+        cmd: PbAdd,
+    },
+    /// Remove things from the database.
+    #[structopt(display_order = 4)]
+    Delete {
+        /// Delete subcommands
+        #[structopt(subcommand)]
+        cmd: PbDelete,
+    },
+    /// Serialize state.
+    #[structopt(display_order = 5)]
+    Export {
+        /// Export subcommands
+        #[structopt(subcommand)]
+        cmd: PbExport,
+    },
+    /// Check the health of the server. Exits non-zero unless it is serving.
+    #[structopt(display_order = 6)]
+    Health {},
 }
@@ -0,0 +1,76 @@
+use packybara::types::IdType;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug, PartialEq)]
+#[structopt(about = "Update entities in db")]
+pub enum PbSet {
+    /// Change the distribution that one or more versionpins resolve to.
+    /// Identify the versionpins either by id (paired with distribution ids),
+    /// or by package and coords (along with the new version).
+    #[structopt(display_order = 1)]
+    VersionPins {
+        /// A description of the change.
+        #[structopt(short, long, display_order = 1)]
+        comment: String,
+        /// The author of the change. Defaults to the current user.
+        #[structopt(short, long, display_order = 2)]
+        author: Option<String>,
+        /// The ids of the versionpins to update. Required unless a package is supplied.
+        #[structopt(
+            short = "v",
+            long = "vpin-ids",
+            display_order = 3,
+            required_unless = "package",
+            requires = "dist-ids"
+        )]
+        vpin_ids: Vec<IdType>,
+        /// The ids of the distributions to resolve to, one per versionpin id.
+        #[structopt(
+            short = "d",
+            long = "dist-ids",
+            display_order = 4,
+            requires = "vpin-ids"
+        )]
+        dist_ids: Vec<IdType>,
+        /// The name of the package whose versionpin should be updated.
+        /// Required unless versionpin ids are supplied.
+        #[structopt(
+            long,
+            display_order = 5,
+            requires = "version",
+            required_unless = "vpin-ids"
+        )]
+        package: Option<String>,
+        /// The version of the package to resolve to.
+        #[structopt(long, display_order = 6, requires = "package")]
+        version: Option<String>,
+        /// The level of the versionpin. Defaults to 'facility'.
+        #[structopt(short = "L", long, display_order = 7)]
+        level: Option<String>,
+        /// The role of the versionpin. Defaults to 'any'.
+        #[structopt(short = "R", long, display_order = 8)]
+        role: Option<String>,
+        /// The platform of the versionpin. Defaults to 'any'.
+        #[structopt(short = "P", long, display_order = 9)]
+        platform: Option<String>,
+        /// The site of the versionpin. Defaults to 'any'.
+        #[structopt(short = "S", long, display_order = 10)]
+        site: Option<String>,
+    },
+    /// Reorder the withs of a versionpin.
+    #[structopt(display_order = 2)]
+    Withs {
+        /// A description of the change.
+        #[structopt(short, long, display_order = 1)]
+        comment: String,
+        /// The author of the change. Defaults to the current user.
+        #[structopt(short, long, display_order = 2)]
+        author: Option<String>,
+        /// The id of the versionpin.
+        #[structopt(name = "VERSIONPIN_ID")]
+        versionpin_id: IdType,
+        /// The versionpin's withs, in their new order.
+        #[structopt(name = "WITH", required = true)]
+        withs: Vec<String>,
+    },
+}
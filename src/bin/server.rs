@@ -1,13 +1,104 @@
 use tokio;
 
-use packybara_grpc::{url_builder, url_builder::UrlBuilder, PackybaraService};
+mod server_cli;
+use packybara_grpc::{PackybaraService, ServerConfig};
+use server_cli::*;
+use structopt::StructOpt;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let url = UrlBuilder::new()
-        .host(url_builder::Host::Localhost)
-        .port(50051)
-        .build();
-    PackybaraService::run(url).await?;
+    let opt = PbServer::from_args();
+    let PbServer {
+        loglevel,
+        host,
+        port,
+        scheme,
+        db_url,
+        config,
+        tls_cert,
+        tls_key,
+        tls_client_ca,
+        auth_tokens,
+        auth_policy,
+        reflection,
+        shutdown_delay_secs,
+        shutdown_grace_secs,
+        cmd,
+    } = opt;
+    let config = ServerConfig::load(config.as_deref())?
+        .db_url_opt(db_url.as_deref())?
+        .bind_opt(scheme.as_deref(), host.as_deref(), port)?
+        .loglevel_opt(loglevel)
+        .tls_cert_opt(tls_cert)
+        .tls_key_opt(tls_key)
+        .tls_client_ca_opt(tls_client_ca)
+        .auth_tokens_opt(auth_tokens)
+        .auth_policy_opt(auth_policy)
+        .reflection_opt(reflection)
+        .shutdown_delay_secs_opt(shutdown_delay_secs)
+        .shutdown_grace_secs_opt(shutdown_grace_secs);
+
+    let mut logger = env_logger::Builder::from_default_env();
+    if let Some(ref level) = config.loglevel {
+        logger.parse_filters(level);
+    }
+    logger.init();
+
+    match cmd {
+        Some(PbServerCmd::CheckConfig {}) => {
+            let addr = config.grpc_url()?.to_socket_addr()?;
+            println!("url: {} ({})", config.url, addr);
+            if config.server_tls_config()?.is_some() {
+                let mode = if config.tls.is_mutual() {
+                    "mutual tls"
+                } else {
+                    "tls"
+                };
+                println!("{} ok", mode);
+            }
+            let (tokens, policy) = config.auth.load()?;
+            if tokens.is_some() {
+                println!("auth tokens ok");
+            }
+            if policy.is_some() {
+                println!("auth policy ok");
+            }
+            if config.reflection {
+                println!("reflection enabled");
+            }
+            println!(
+                "db: {}@{}:{}/{}",
+                config.db.user, config.db.host, config.db.port, config.db.dbname
+            );
+            config.db.check_connection().await?;
+            println!("database connection ok");
+        }
+        None => PackybaraService::run(config, shutdown_signal()).await?,
+    }
     Ok(())
 }
+
+// Resolves when the process is asked to shut down, via SIGINT or SIGTERM
+#[cfg(unix)]
+async fn shutdown_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(e) => {
+            log::error!("unable to listen for SIGTERM: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        }
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => log::info!("received SIGINT"),
+        _ = terminate.recv() => log::info!("received SIGTERM"),
+    }
+}
+
+// Resolves when the process is asked to shut down, via ctrl-c
+#[cfg(not(unix))]
+async fn shutdown_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+    log::info!("received ctrl-c");
+}
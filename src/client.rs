@@ -1,26 +1,584 @@
 use crate::{
-    url as grpcurl, Coords, PackybaraClient, VersionPinQueryReply, VersionPinQueryRequest,
-    VersionPinsQueryReply, VersionPinsQueryRequest, VersionPinsQueryRow,
+    auth,
+    health::{HealthCheckRequest, HealthClient, ServingStatus, SERVICE_NAME},
+    url as grpcurl, AddReply, ChangeAction, ChangesQueryReply, ChangesQueryRequest,
+    ChangesQueryRow, ChangesetOpResult, ChangesetReply, ChangesetRequest, Coords,
+    DistributionsDeleteReply, DistributionsDeleteRequest, DistributionsQueryReply,
+    DistributionsQueryRequest, DistributionsQueryRow, LevelsAddRequest, LevelsQueryReply,
+    LevelsQueryRequest, PackagesAddRequest, PackagesQueryReply, PackagesQueryRequest,
+    PackagesXmlChunk, PackagesXmlRequest, PackybaraClient, PinsQueryReply, PinsQueryRequest,
+    PinsQueryRow, PkgCoordsDeleteReply, PkgCoordsDeleteRequest, PkgCoordsQueryReply,
+    PkgCoordsQueryRequest, PkgCoordsQueryRow, PlatformsAddRequest, PlatformsQueryReply,
+    PlatformsQueryRequest, RevisionsQueryReply, RevisionsQueryRequest, RevisionsQueryRow,
+    RolesAddRequest, RolesQueryReply, RolesQueryRequest, RolesQueryRow, SitesQueryReply,
+    SitesQueryRequest, VersionPinQueryReply, VersionPinQueryRequest, VersionPinWithsQueryReply,
+    VersionPinWithsQueryRequest, VersionPinWithsQueryRow, VersionPinsAddRequest,
+    VersionPinsDeleteReply, VersionPinsDeleteRequest, VersionPinsQueryReply,
+    VersionPinsQueryRequest, VersionPinsQueryRow, VersionPinsSetRequest, VersionPinsWriteReply,
+    WithsAddRequest, WithsDeleteReply, WithsDeleteRequest, WithsQueryReply, WithsQueryRequest,
+    WithsQueryRow, WithsRemoveRequest, WithsReorderRequest, WithsWriteReply,
 };
+use chrono::{DateTime, Local, TimeZone};
+use packybara::db::find::pins::FindPinsRow;
+use packybara::db::find::versionpin_withs::FindVersionPinWithsRow;
 use packybara::db::find::versionpins::FindVersionPinsRow;
+use packybara::db::find::withs::FindWithsRow;
+use packybara::db::find_all::changes::{ChangeAction as PChangeAction, FindAllChangesRow};
+use packybara::db::find_all::distributions::FindAllDistributionsRow;
+use packybara::db::find_all::levels::FindAllLevelsRow;
+use packybara::db::find_all::packages::FindAllPackagesRow;
+use packybara::db::find_all::pkgcoords::FindAllPkgCoordsRow;
+use packybara::db::find_all::platforms::FindAllPlatformsRow;
+use packybara::db::find_all::revisions::FindAllRevisionsRow;
+use packybara::db::find_all::roles::FindAllRolesRow;
+use packybara::db::find_all::sites::FindAllSitesRow;
 use packybara::db::find_all::versionpins::FindAllVersionPinsRow;
+use packybara::types::{IdType, LongIdType};
+use snafu::{IntoError, ResultExt, Snafu};
+use std::collections::VecDeque;
 use std::convert::TryFrom;
+use std::io::Write;
+use std::path::PathBuf;
+use tokio::stream::{Stream, StreamExt};
+use tonic::metadata::{errors::InvalidMetadataValue, MetadataValue};
 use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Request, Status};
+
+/// Boxed error used as the source of errors whose cause varies
+pub type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// Errors returned by the Client
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// The url supplied to the client could not be converted to an endpoint
+    #[snafu(display("Invalid url '{}': {}", url, source))]
+    InvalidUrl { url: String, source: BoxError },
+    /// Failure to connect to, or communicate with, the server
+    #[snafu(display("Transport error: {}", source))]
+    Transport { source: tonic::transport::Error },
+    /// The requested entity does not exist
+    #[snafu(display("Not found: {}", source.message()))]
+    NotFound { source: Status },
+    /// The server rejected the request's arguments
+    #[snafu(display("Invalid argument: {}", source.message()))]
+    InvalidArgument { source: Status },
+    /// The server, or its database, is unavailable
+    #[snafu(display("Unavailable: {}", source.message()))]
+    Unavailable { source: Status },
+    /// Any other error status returned by the server
+    #[snafu(display("Server returned {:?}: {}", source.code(), source.message()))]
+    Rpc { source: Status },
+    /// A field in the server's reply could not be decoded
+    #[snafu(display("Unable to decode {}: {}", field, source))]
+    Decode { field: String, source: BoxError },
+    /// A document streamed from the server could not be written
+    #[snafu(display("Unable to write {}: {}", target, source))]
+    Output {
+        target: String,
+        source: std::io::Error,
+    },
+    /// A certificate or key named by the tls options could not be read
+    #[snafu(display("Unable to read {} {}: {}", name, path.display(), source))]
+    ReadPem {
+        name: String,
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The bearer token cannot be sent as metadata
+    #[snafu(display("Invalid bearer token: {}", source))]
+    InvalidToken { source: InvalidMetadataValue },
+}
+
+impl Error {
+    /// Retrieve the grpc status code, if the error originated with the server
+    pub fn code(&self) -> Option<Code> {
+        match self {
+            Self::NotFound { source }
+            | Self::InvalidArgument { source }
+            | Self::Unavailable { source }
+            | Self::Rpc { source } => Some(source.code()),
+            _ => None,
+        }
+    }
+}
+
+impl From<Status> for Error {
+    fn from(status: Status) -> Self {
+        match status.code() {
+            Code::NotFound => NotFound.into_error(status),
+            Code::InvalidArgument => InvalidArgument.into_error(status),
+            Code::Unavailable => Unavailable.into_error(status),
+            _ => Rpc.into_error(status),
+        }
+    }
+}
+
+/// Result type returned by the Client
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Convert an id received from the server into an i32
+fn decode_id(field: &str, id: i64) -> Result<i32> {
+    i32::try_from(id)
+        .map_err(BoxError::from)
+        .context(Decode { field })
+}
+
+/// Convert a timestamp received from the server into a DateTime
+fn decode_timestamp(field: &str, timestamp: &prost_types::Timestamp) -> Result<DateTime<Local>> {
+    let nanos = u32::try_from(timestamp.nanos)
+        .map_err(BoxError::from)
+        .context(Decode { field })?;
+    Local
+        .timestamp_opt(timestamp.seconds, nanos)
+        .single()
+        .ok_or_else(|| BoxError::from(format!("invalid timestamp {:?}", timestamp)))
+        .context(Decode { field })
+}
+
+/// Convert a ChangeAction received from the server into its packybara equivalent
+fn decode_change_action(field: &str, action: i32) -> Result<PChangeAction> {
+    let action = ChangeAction::from_i32(action)
+        .ok_or_else(|| BoxError::from(format!("unknown change action {}", action)))
+        .context(Decode { field })?;
+    Ok(match action {
+        ChangeAction::Insert => PChangeAction::Insert,
+        ChangeAction::Update => PChangeAction::Update,
+        ChangeAction::Delete => PChangeAction::Delete,
+    })
+}
+
+/// Convert a versionpin received from the server into a FindAllVersionPinsRow
+fn decode_versionpins_row(vpin: VersionPinsQueryRow) -> Result<FindAllVersionPinsRow> {
+    let VersionPinsQueryRow {
+        versionpin_id,
+        distribution_id,
+        pkgcoord_id,
+        distribution,
+        coords:
+            Coords {
+                level,
+                role,
+                platform,
+                site,
+            },
+        withs,
+    } = vpin;
+    let withs = if withs.len() > 0 { Some(withs) } else { None };
+    Ok(FindAllVersionPinsRow::from_parts(
+        decode_id("versionpin_id", versionpin_id)?,
+        decode_id("distribution_id", distribution_id)?,
+        decode_id("pkgcoord_id", pkgcoord_id)?,
+        &distribution,
+        &level,
+        &role,
+        &platform,
+        &site,
+        withs,
+    ))
+}
+
+/// Convert a with received from the server into a FindVersionPinWithsRow
+fn decode_versionpin_withs_row(with: VersionPinWithsQueryRow) -> Result<FindVersionPinWithsRow> {
+    let VersionPinWithsQueryRow {
+        id,
+        versionpin_id,
+        with,
+        order,
+        ..
+    } = with;
+    Ok(FindVersionPinWithsRow::from_parts(
+        decode_id("id", id)?,
+        decode_id("versionpin_id", versionpin_id)?,
+        &with,
+        order,
+    ))
+}
+
+/// Convert a with received from the server, along with the versionpin it
+/// resolves to, into a ResolvedWith
+fn decode_resolved_with(with: VersionPinWithsQueryRow) -> Result<ResolvedWith> {
+    let resolved = with
+        .resolved
+        .clone()
+        .ok_or_else(|| BoxError::from("the with was not resolved"))
+        .context(Decode { field: "resolved" })?;
+    let resolved = decode_withs_row(resolved)?;
+    Ok(ResolvedWith {
+        with: decode_versionpin_withs_row(with)?,
+        resolved,
+    })
+}
+
+/// Convert a resolved with received from the server into a FindWithsRow
+fn decode_withs_row(with: WithsQueryRow) -> Result<FindWithsRow> {
+    let WithsQueryRow {
+        versionpin_id,
+        distribution,
+        coords:
+            Coords {
+                level,
+                role,
+                platform,
+                site,
+            },
+    } = with;
+    Ok(FindWithsRow::from_parts(
+        decode_id("versionpin_id", versionpin_id)?,
+        &distribution,
+        &level,
+        &role,
+        &platform,
+        &site,
+    ))
+}
+
+/// Convert a distribution received from the server into a FindAllDistributionsRow
+fn decode_distributions_row(
+    distribution: DistributionsQueryRow,
+) -> Result<FindAllDistributionsRow> {
+    let DistributionsQueryRow {
+        id,
+        package,
+        version,
+    } = distribution;
+    Ok(FindAllDistributionsRow {
+        id: decode_id("id", id)?,
+        package,
+        version,
+    })
+}
+
+/// Convert a pkgcoord received from the server into a FindAllPkgCoordsRow
+fn decode_pkgcoords_row(pkgcoord: PkgCoordsQueryRow) -> Result<FindAllPkgCoordsRow> {
+    let PkgCoordsQueryRow {
+        id,
+        package,
+        coords:
+            Coords {
+                level,
+                role,
+                platform,
+                site,
+            },
+    } = pkgcoord;
+    Ok(FindAllPkgCoordsRow {
+        id: decode_id("id", id)?,
+        package,
+        level,
+        role,
+        platform,
+        site,
+    })
+}
+
+/// The outcome of a request which modifies the database
+#[derive(Debug, PartialEq, Eq)]
+pub struct WriteResult {
+    /// The number of rows updated
+    pub updates: u64,
+    /// The id of the revision recorded for the change
+    pub revision_id: IdType,
+    /// The id of the transaction in which the change was made
+    pub transaction_id: LongIdType,
+}
+
+impl TryFrom<AddReply> for WriteResult {
+    type Error = Error;
+
+    fn try_from(reply: AddReply) -> Result<Self> {
+        let AddReply {
+            updates,
+            revision_id,
+            transaction_id,
+        } = reply;
+        let updates = u64::try_from(updates)
+            .map_err(BoxError::from)
+            .context(Decode { field: "updates" })?;
+        Ok(Self {
+            updates,
+            revision_id: decode_id("revision_id", revision_id)?,
+            transaction_id: transaction_id as LongIdType,
+        })
+    }
+}
+
+/// The versionpins created or updated by a request, along with the revision
+/// recorded for the change
+#[derive(Debug)]
+pub struct VersionPinsWriteResult {
+    /// The versionpins as they stand after the change
+    pub vpins: Vec<FindAllVersionPinsRow>,
+    /// The id of the revision recorded for the change
+    pub revision_id: IdType,
+    /// The id of the transaction in which the change was made
+    pub transaction_id: LongIdType,
+}
+
+impl TryFrom<VersionPinsWriteReply> for VersionPinsWriteResult {
+    type Error = Error;
+
+    fn try_from(reply: VersionPinsWriteReply) -> Result<Self> {
+        let VersionPinsWriteReply {
+            vpins,
+            revision_id,
+            transaction_id,
+        } = reply;
+        Ok(Self {
+            vpins: vpins
+                .into_iter()
+                .map(decode_versionpins_row)
+                .collect::<Result<Vec<_>>>()?,
+            revision_id: decode_id("revision_id", revision_id)?,
+            transaction_id: transaction_id as LongIdType,
+        })
+    }
+}
+
+/// A with of a versionpin, along with the versionpin it resolves to at the
+/// coords of the versionpin it belongs to
+#[derive(Debug)]
+pub struct ResolvedWith {
+    /// The with, as recorded against its versionpin
+    pub with: FindVersionPinWithsRow,
+    /// The versionpin the with resolves to
+    pub resolved: FindWithsRow,
+}
+
+/// The withs of a versionpin after a change, along with the revision
+/// recorded for the change
+#[derive(Debug)]
+pub struct WithsWriteResult {
+    /// The withs of the versionpin, in order
+    pub withs: Vec<FindVersionPinWithsRow>,
+    /// The id of the revision recorded for the change
+    pub revision_id: IdType,
+    /// The id of the transaction in which the change was made
+    pub transaction_id: LongIdType,
+}
+
+impl TryFrom<WithsWriteReply> for WithsWriteResult {
+    type Error = Error;
+
+    fn try_from(reply: WithsWriteReply) -> Result<Self> {
+        let WithsWriteReply {
+            withs,
+            revision_id,
+            transaction_id,
+        } = reply;
+        Ok(Self {
+            withs: withs
+                .into_iter()
+                .map(decode_versionpin_withs_row)
+                .collect::<Result<Vec<_>>>()?,
+            revision_id: decode_id("revision_id", revision_id)?,
+            transaction_id: transaction_id as LongIdType,
+        })
+    }
+}
+
+/// The rows removed by a delete request, along with the revision recorded
+/// for the change. When the request is a dry run, the rows are those which
+/// would have been removed, and neither revision_id nor transaction_id is set.
+#[derive(Debug)]
+pub struct DeleteResult<T> {
+    /// The rows removed, or which would be removed
+    pub rows: Vec<T>,
+    /// The id of the revision recorded for the change
+    pub revision_id: Option<IdType>,
+    /// The id of the transaction in which the change was made
+    pub transaction_id: Option<LongIdType>,
+}
+
+impl<T> DeleteResult<T> {
+    // Decode the parts of a delete reply
+    fn from_reply<R, F>(
+        rows: Vec<R>,
+        revision_id: Option<i64>,
+        transaction_id: Option<i64>,
+        decode: F,
+    ) -> Result<Self>
+    where
+        F: FnMut(R) -> Result<T>,
+    {
+        Ok(Self {
+            rows: rows.into_iter().map(decode).collect::<Result<Vec<_>>>()?,
+            revision_id: revision_id
+                .map(|id| decode_id("revision_id", id))
+                .transpose()?,
+            transaction_id: transaction_id.map(|id| id as LongIdType),
+        })
+    }
+}
+
+/// The outcome of applying a changeset
+#[derive(Debug)]
+pub struct ChangesetResult {
+    /// The number of rows added, updated or deleted by each op, in order
+    pub rows: Vec<u64>,
+    /// The id of the revision recorded for the changeset
+    pub revision_id: IdType,
+    /// The id of the transaction in which the changeset was applied
+    pub transaction_id: LongIdType,
+}
+
+impl TryFrom<ChangesetReply> for ChangesetResult {
+    type Error = Error;
+
+    fn try_from(reply: ChangesetReply) -> Result<Self> {
+        let ChangesetReply {
+            mut results,
+            revision_id,
+            transaction_id,
+        } = reply;
+        results.sort_by_key(|result| result.index);
+        let rows = results
+            .into_iter()
+            .map(|ChangesetOpResult { rows, .. }| {
+                u64::try_from(rows)
+                    .map_err(BoxError::from)
+                    .context(Decode { field: "rows" })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            rows,
+            revision_id: decode_id("revision_id", revision_id)?,
+            transaction_id: transaction_id as LongIdType,
+        })
+    }
+}
+
+/// Iterates over the versionpins matching a query, a page at a time,
+/// requesting the next page from the server once the current one is exhausted.
+pub struct VersionPinsPages {
+    client: PackybaraClient<Channel>,
+    request: VersionPinsQueryRequest,
+    rows: VecDeque<FindAllVersionPinsRow>,
+    done: bool,
+}
+
+impl VersionPinsPages {
+    /// Retrieve the next versionpin, requesting the next page from the server
+    /// if need be. Returns None once every page has been consumed.
+    pub async fn next(&mut self) -> Option<Result<FindAllVersionPinsRow>> {
+        while self.rows.is_empty() && !self.done {
+            if let Err(e) = self.fetch().await {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+        self.rows.pop_front().map(Ok)
+    }
+
+    // Request the next page, buffering its rows
+    async fn fetch(&mut self) -> Result<()> {
+        let request = tonic::Request::new(self.request.clone());
+        let response = self.client.get_version_pins(request).await?;
+        let VersionPinsQueryReply {
+            vpins,
+            next_page_token,
+        } = response.into_inner();
+        for vpin in vpins {
+            self.rows.push_back(decode_versionpins_row(vpin)?);
+        }
+        match next_page_token {
+            Some(token) if !token.is_empty() => self.request.page_token = Some(token),
+            _ => self.done = true,
+        }
+        Ok(())
+    }
+}
 
 // this has some implications for applications that want to communicate
 // in multiple channels. If this becomes a requirement, we will have to
 // put an arc around client
 pub struct Client {
     client: PackybaraClient<Channel>,
+    health: HealthClient<Channel>,
 }
 
 impl Client {
-    /// create a new client instance , given a url
-    pub async fn new(url: grpcurl::GrpcUrl) -> Result<Self, Box<dyn std::error::Error>> {
+    /// create a new client instance , given a url. An https url is
+    /// connected to using the default tls options.
+    pub async fn new(url: grpcurl::GrpcUrl) -> Result<Self> {
+        Self::with_tls(url, tls::Options::new()).await
+    }
+
+    /// Create a new client instance, given a url and the tls options used to
+    /// connect to it. The options are ignored unless the url is https.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The url of the server
+    /// * `options` - tls::Options instance, naming the CA, client identity and domain
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - Client
+    /// - Err - client::Error
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let client = Client::with_tls(
+    ///     url,
+    ///     tls::Options::new()
+    ///         .ca("/etc/packybara/ca.pem")
+    ///         .identity("client.pem", "client.key")
+    ///         .domain("packybara.example.com"),
+    /// ).await?;
+    /// ```
+    pub async fn with_tls(url: grpcurl::GrpcUrl, options: tls::Options) -> Result<Self> {
+        Self::connect(url, options, None).await
+    }
+
+    /// Create a new client instance, given a url, the tls options used to
+    /// connect to it, and an optional bearer token sent with each request to
+    /// authenticate the client.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The url of the server
+    /// * `options` - tls::Options instance, naming the CA, client identity and domain
+    /// * `token` - The bearer token, if the server authenticates requests
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - Client
+    /// - Err - client::Error
+    pub async fn connect(
+        url: grpcurl::GrpcUrl,
+        options: tls::Options,
+        token: Option<String>,
+    ) -> Result<Self> {
+        let https = url.scheme() == "https";
         let url = url.as_str().to_string();
-        let endpoint = Endpoint::try_from(url)?;
-        let client = PackybaraClient::connect(endpoint).await?;
-        Ok(Client { client })
+        let endpoint = Endpoint::try_from(url.clone())
+            .map_err(BoxError::from)
+            .context(InvalidUrl { url })?;
+        let channel = if https {
+            let tls = options.client_tls_config()?;
+            endpoint.tls_config(&tls).connect().await
+        } else {
+            endpoint.connect().await
+        }
+        .context(Transport)?;
+        let health = HealthClient::new(channel.clone());
+        let client = match token {
+            Some(token) => {
+                let bearer =
+                    MetadataValue::from_str(&format!("Bearer {}", token)).context(InvalidToken)?;
+                PackybaraClient::with_interceptor(channel, move |mut request: Request<()>| {
+                    request
+                        .metadata_mut()
+                        .insert(auth::AUTHORIZATION_KEY, bearer.clone());
+                    Ok(request)
+                })
+            }
+            None => PackybaraClient::new(channel),
+        };
+        Ok(Client { client, health })
     }
     /// Retrieve versionpin from server, given GetVersionPinOptions instance
     ///
@@ -32,7 +590,7 @@ impl Client {
     ///
     /// * Result
     /// - Ok - FindVersionPinsRow
-    /// - Err - Boxed std::error::Error
+    /// - Err - client::Error
     ///
     /// # Example
     ///
@@ -42,7 +600,7 @@ impl Client {
     pub async fn get_version_pin(
         &mut self,
         options: get_versionpin::Options,
-    ) -> Result<FindVersionPinsRow, Box<dyn std::error::Error>> {
+    ) -> Result<FindVersionPinsRow> {
         let get_versionpin::Options {
             package,
             level,
@@ -74,7 +632,7 @@ impl Client {
         let withs = if withs.len() > 0 { Some(withs) } else { None };
 
         let response = FindVersionPinsRow::from_parts(
-            versionpin_id as i32,
+            decode_id("versionpin_id", versionpin_id)?,
             distribution.as_str(),
             level.as_str(),
             role.as_str(),
@@ -88,73 +646,1878 @@ impl Client {
     pub async fn get_version_pins(
         &mut self,
         options: get_versionpins::Options,
-    ) -> Result<Vec<FindAllVersionPinsRow>, Box<dyn std::error::Error>> {
-        let get_versionpins::Options {
+    ) -> Result<Vec<FindAllVersionPinsRow>> {
+        let request = tonic::Request::new(options.into_request());
+        let response = self.client.get_version_pins(request).await?;
+        let VersionPinsQueryReply { vpins, .. } = response.into_inner();
+
+        let results = vpins
+            .into_iter()
+            .map(decode_versionpins_row)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(results)
+    }
+
+    /// Page through versionpins from the server, given a get_versionpins::Options
+    /// instance, retrieving `page_size` rows per request. The pages are followed
+    /// transparently as the returned VersionPinsPages is advanced.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - get_versionpins::Options instance, encapsulating the query parameters
+    /// * `page_size` - The number of rows to retrieve per request
+    ///
+    /// # Returns
+    ///
+    /// * VersionPinsPages
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut vpins = client.version_pins_pages(get_versionpins::Options::new(), 500);
+    /// while let Some(vpin) = vpins.next().await {
+    ///     println!("{:?}", vpin?);
+    /// }
+    /// ```
+    pub fn version_pins_pages(
+        &self,
+        options: get_versionpins::Options,
+        page_size: u32,
+    ) -> VersionPinsPages {
+        let mut request = options.into_request();
+        request.page_size = Some(page_size);
+        VersionPinsPages {
+            client: self.client.clone(),
+            request,
+            rows: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Stream versionpins from the server, given a get_versionpins::Options
+    /// instance. Rows are yielded as the server reads them from the database,
+    /// so that arbitrarily large results need not be held in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - get_versionpins::Options instance, encapsulating the query parameters
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - Stream of Result wrapped FindAllVersionPinsRow
+    /// - Err - client::Error
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut vpins = client
+    ///     .stream_version_pins(get_versionpins::Options::new().level_opt(Some("dev01".into())))
+    ///     .await?;
+    /// while let Some(vpin) = vpins.next().await {
+    ///     println!("{:?}", vpin?);
+    /// }
+    /// ```
+    pub async fn stream_version_pins(
+        &mut self,
+        options: get_versionpins::Options,
+    ) -> Result<impl Stream<Item = Result<FindAllVersionPinsRow>>> {
+        let request = tonic::Request::new(options.into_request());
+        let response = self.client.stream_version_pins(request).await?;
+        Ok(response
+            .into_inner()
+            .map(|vpin| vpin.map_err(Error::from).and_then(decode_versionpins_row)))
+    }
+    /// Retrieve roles from the server, given a get_roles::Options instance
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - get_roles::Options instance, encapsulating the query parameters
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - Vector of FindAllRolesRow
+    /// - Err - client::Error
+    pub async fn get_roles(&mut self, options: get_roles::Options) -> Result<Vec<FindAllRolesRow>> {
+        let get_roles::Options {
+            role,
+            category,
+            order_by,
+        } = options;
+        let request = tonic::Request::new(RolesQueryRequest {
+            role,
+            category,
+            order_by,
+        });
+        let response = self.client.get_roles(request).await?;
+        let RolesQueryReply { roles } = response.into_inner();
+        let results = roles
+            .into_iter()
+            .map(|RolesQueryRow { role, category }| FindAllRolesRow { role, category })
+            .collect::<Vec<_>>();
+        Ok(results)
+    }
+
+    /// Retrieve platforms from the server, given a get_platforms::Options instance
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - get_platforms::Options instance, encapsulating the query parameters
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - Vector of FindAllPlatformsRow
+    /// - Err - client::Error
+    pub async fn get_platforms(
+        &mut self,
+        options: get_platforms::Options,
+    ) -> Result<Vec<FindAllPlatformsRow>> {
+        let get_platforms::Options { platform, order_by } = options;
+        let request = tonic::Request::new(PlatformsQueryRequest { platform, order_by });
+        let response = self.client.get_platforms(request).await?;
+        let PlatformsQueryReply { names } = response.into_inner();
+        let results = names
+            .into_iter()
+            .map(|name| FindAllPlatformsRow { name })
+            .collect::<Vec<_>>();
+        Ok(results)
+    }
+
+    /// Retrieve sites from the server, given a get_sites::Options instance
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - get_sites::Options instance, encapsulating the query parameters
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - Vector of FindAllSitesRow
+    /// - Err - client::Error
+    pub async fn get_sites(&mut self, options: get_sites::Options) -> Result<Vec<FindAllSitesRow>> {
+        let get_sites::Options { site } = options;
+        let request = tonic::Request::new(SitesQueryRequest { site });
+        let response = self.client.get_sites(request).await?;
+        let SitesQueryReply { names } = response.into_inner();
+        let results = names
+            .into_iter()
+            .map(|name| FindAllSitesRow { name })
+            .collect::<Vec<_>>();
+        Ok(results)
+    }
+
+    /// Retrieve levels from the server, given a get_levels::Options instance
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - get_levels::Options instance, encapsulating the query parameters
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - Vector of FindAllLevelsRow
+    /// - Err - client::Error
+    pub async fn get_levels(
+        &mut self,
+        options: get_levels::Options,
+    ) -> Result<Vec<FindAllLevelsRow>> {
+        let get_levels::Options {
+            level,
+            show,
+            depth,
+            order_by,
+        } = options;
+        let request = tonic::Request::new(LevelsQueryRequest {
+            level,
+            show,
+            depth: depth.map(u32::from),
+            order_by,
+        });
+        let response = self.client.get_levels(request).await?;
+        let LevelsQueryReply { levels } = response.into_inner();
+        let results = levels
+            .into_iter()
+            .map(|level| FindAllLevelsRow { level })
+            .collect::<Vec<_>>();
+        Ok(results)
+    }
+    /// Retrieve the names of all packages from the server
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - Vector of FindAllPackagesRow
+    /// - Err - client::Error
+    pub async fn get_packages(&mut self) -> Result<Vec<FindAllPackagesRow>> {
+        let request = tonic::Request::new(PackagesQueryRequest {});
+        let response = self.client.get_packages(request).await?;
+        let PackagesQueryReply { names } = response.into_inner();
+        let results = names
+            .into_iter()
+            .map(|name| FindAllPackagesRow { name })
+            .collect::<Vec<_>>();
+        Ok(results)
+    }
+
+    /// Retrieve distributions from the server, given a get_distributions::Options instance
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - get_distributions::Options instance, encapsulating the query parameters
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - Vector of FindAllDistributionsRow
+    /// - Err - client::Error
+    pub async fn get_distributions(
+        &mut self,
+        options: get_distributions::Options,
+    ) -> Result<Vec<FindAllDistributionsRow>> {
+        let get_distributions::Options {
+            package,
+            version,
+            order_direction,
+        } = options;
+        let request = tonic::Request::new(DistributionsQueryRequest {
             package,
             version,
+            order_direction: order_direction.map(|dir| dir as i32),
+        });
+        let response = self.client.get_distributions(request).await?;
+        let DistributionsQueryReply { distributions } = response.into_inner();
+        let results = distributions
+            .into_iter()
+            .map(decode_distributions_row)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(results)
+    }
+    /// Retrieve package coordinates from the server, given a get_pkgcoords::Options instance
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - get_pkgcoords::Options instance, encapsulating the query parameters
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - Vector of FindAllPkgCoordsRow
+    /// - Err - client::Error
+    pub async fn get_pkgcoords(
+        &mut self,
+        options: get_pkgcoords::Options,
+    ) -> Result<Vec<FindAllPkgCoordsRow>> {
+        let get_pkgcoords::Options {
+            package,
             level,
             role,
             platform,
             site,
-            isolate_facility,
             search_mode,
             order_by,
-            order_direction,
         } = options;
-        let request = tonic::Request::new(VersionPinsQueryRequest {
+        let request = tonic::Request::new(PkgCoordsQueryRequest {
             package,
-            version,
             level,
             role,
             platform,
             site,
-            isolate_facility,
-            search_mode,
             order_by,
-            order_direction,
-            full_withs: Some(false),
-            limit: None,
+            search_mode: search_mode.map(|mode| mode as i32),
         });
-        let response = self.client.get_version_pins(request).await?;
-        let VersionPinsQueryReply { vpins } = response.into_inner();
-
-        let results = vpins
+        let response = self.client.get_pkg_coords(request).await?;
+        let PkgCoordsQueryReply { pkgcoords } = response.into_inner();
+        let results = pkgcoords
             .into_iter()
-            .map(|vpin| {
-                let VersionPinsQueryRow {
-                    versionpin_id,
-                    distribution_id,
-                    pkgcoord_id,
-                    distribution,
-                    coords:
-                        Coords {
-                            level,
-                            role,
-                            platform,
-                            site,
-                        },
-                    withs,
-                } = vpin;
-                let withs = if withs.len() > 0 { Some(withs) } else { None };
-                FindAllVersionPinsRow::from_parts(
-                    versionpin_id as i32,
-                    distribution_id as i32,
-                    pkgcoord_id as i32,
-                    &distribution,
-                    &level,
-                    &role,
-                    &platform,
-                    &site,
-                    withs,
-                )
-            })
-            .collect::<Vec<_>>();
+            .map(decode_pkgcoords_row)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(results)
+    }
+
+    /// Retrieve pins from the server, given a get_pins::Options instance
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - get_pins::Options instance, encapsulating the query parameters
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - Vector of FindPinsRow
+    /// - Err - client::Error
+    pub async fn get_pins(&mut self, options: get_pins::Options) -> Result<Vec<FindPinsRow>> {
+        let get_pins::Options {
+            level,
+            role,
+            platform,
+            site,
+            search_mode,
+            order_by,
+            limit,
+        } = options;
+        let request = tonic::Request::new(PinsQueryRequest {
+            level,
+            role,
+            platform,
+            site,
+            search_mode: search_mode.map(|mode| mode as i32),
+            order_by: order_by
+                .into_iter()
+                .flatten()
+                .map(|attr| attr as i32)
+                .collect(),
+            limit: limit.map(|x| x as i64),
+        });
+        let response = self.client.get_pins(request).await?;
+        let PinsQueryReply { pins } = response.into_inner();
+        let results = pins
+            .into_iter()
+            .map(|pin| {
+                let PinsQueryRow {
+                    id,
+                    coords:
+                        Coords {
+                            level,
+                            role,
+                            platform,
+                            site,
+                        },
+                } = pin;
+                Ok(FindPinsRow::from_parts(
+                    decode_id("id", id)?,
+                    &level,
+                    &role,
+                    &platform,
+                    &site,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(results)
+    }
+    /// Retrieve the withs of a package, resolved to distributions at the
+    /// supplied pin coords, given a get_withs::Options instance
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - get_withs::Options instance, encapsulating the query parameters
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - Vector of FindWithsRow
+    /// - Err - client::Error
+    pub async fn get_withs(&mut self, options: get_withs::Options) -> Result<Vec<FindWithsRow>> {
+        let get_withs::Options {
+            package,
+            level,
+            role,
+            platform,
+            site,
+        } = options;
+        let request = tonic::Request::new(WithsQueryRequest {
+            package,
+            level,
+            role,
+            platform,
+            site,
+        });
+        let response = self.client.get_withs(request).await?;
+        let WithsQueryReply { withs } = response.into_inner();
+        let results = withs
+            .into_iter()
+            .map(decode_withs_row)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(results)
+    }
+
+    /// Retrieve the withs of a versionpin, given its id, each along with the
+    /// versionpin it resolves to at the versionpin's coords
+    ///
+    /// # Arguments
+    ///
+    /// * `versionpin_id` - The id of the versionpin
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - Vector of ResolvedWith
+    /// - Err - client::Error
+    pub async fn get_version_pin_withs(
+        &mut self,
+        versionpin_id: IdType,
+    ) -> Result<Vec<ResolvedWith>> {
+        let request = tonic::Request::new(VersionPinWithsQueryRequest {
+            versionpin_id: versionpin_id as i64,
+        });
+        let response = self.client.get_version_pin_withs(request).await?;
+        let VersionPinWithsQueryReply { withs } = response.into_inner();
+        let results = withs
+            .into_iter()
+            .map(decode_resolved_with)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(results)
+    }
+    /// Retrieve revisions from the server, given a get_revisions::Options instance
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - get_revisions::Options instance, encapsulating the query parameters
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - Vector of FindAllRevisionsRow
+    /// - Err - client::Error
+    pub async fn get_revisions(
+        &mut self,
+        options: get_revisions::Options,
+    ) -> Result<Vec<FindAllRevisionsRow>> {
+        let get_revisions::Options {
+            id,
+            transaction_id,
+            author,
+            order_by,
+            order_direction,
+            limit,
+        } = options;
+        let request = tonic::Request::new(RevisionsQueryRequest {
+            id: id.map(|x| x as i64),
+            transaction_id: transaction_id.map(|x| x as i64),
+            author,
+            order_by,
+            limit: limit.map(|x| x as i64),
+            order_direction: order_direction.map(|dir| dir as i32),
+        });
+        let response = self.client.get_revisions(request).await?;
+        let RevisionsQueryReply { revisions } = response.into_inner();
+        let results = revisions
+            .into_iter()
+            .map(
+                |RevisionsQueryRow {
+                     id,
+                     transaction_id,
+                     author,
+                     comment,
+                     datetime,
+                 }| {
+                    Ok(FindAllRevisionsRow {
+                        id: decode_id("id", id)?,
+                        transaction_id: transaction_id as LongIdType,
+                        author,
+                        comment,
+                        datetime: decode_timestamp("datetime", &datetime)?,
+                    })
+                },
+            )
+            .collect::<Result<Vec<_>>>()?;
+        Ok(results)
+    }
+
+    /// Retrieve the changes made by a transaction, given its id
+    ///
+    /// # Arguments
+    ///
+    /// * `transaction_id` - The id of the transaction
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - Vector of FindAllChangesRow
+    /// - Err - client::Error
+    pub async fn get_changes(
+        &mut self,
+        transaction_id: LongIdType,
+    ) -> Result<Vec<FindAllChangesRow>> {
+        let request = tonic::Request::new(ChangesQueryRequest {
+            transaction_id: transaction_id as i64,
+        });
+        let response = self.client.get_changes(request).await?;
+        let ChangesQueryReply { changes } = response.into_inner();
+        let results = changes
+            .into_iter()
+            .map(|change| {
+                let ChangesQueryRow {
+                    id,
+                    transaction_id,
+                    action,
+                    coords:
+                        Coords {
+                            level,
+                            role,
+                            platform,
+                            site,
+                        },
+                    package,
+                    old,
+                    new,
+                } = change;
+                Ok(FindAllChangesRow::from_parts(
+                    decode_id("id", id)?,
+                    transaction_id as LongIdType,
+                    decode_change_action("action", action)?,
+                    &level,
+                    &role,
+                    &platform,
+                    &site,
+                    &package,
+                    &old,
+                    &new,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(results)
+    }
+    /// Add one or more packages to the database in a single transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - add::Options instance, encapsulating the packages, author and comment
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - WriteResult
+    /// - Err - client::Error
+    pub async fn add_packages(&mut self, options: add::Options) -> Result<WriteResult> {
+        let add::Options {
+            names,
+            author,
+            comment,
+        } = options;
+        let request = tonic::Request::new(PackagesAddRequest {
+            names,
+            author,
+            comment,
+        });
+        let response = self.client.add_packages(request).await?;
+        WriteResult::try_from(response.into_inner())
+    }
+
+    /// Add one or more levels to the database in a single transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - add::Options instance, encapsulating the levels, author and comment
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - WriteResult
+    /// - Err - client::Error
+    pub async fn add_levels(&mut self, options: add::Options) -> Result<WriteResult> {
+        let add::Options {
+            names,
+            author,
+            comment,
+        } = options;
+        let request = tonic::Request::new(LevelsAddRequest {
+            names,
+            author,
+            comment,
+        });
+        let response = self.client.add_levels(request).await?;
+        WriteResult::try_from(response.into_inner())
+    }
+
+    /// Add one or more roles to the database in a single transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - add::Options instance, encapsulating the roles, author and comment
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - WriteResult
+    /// - Err - client::Error
+    pub async fn add_roles(&mut self, options: add::Options) -> Result<WriteResult> {
+        let add::Options {
+            names,
+            author,
+            comment,
+        } = options;
+        let request = tonic::Request::new(RolesAddRequest {
+            names,
+            author,
+            comment,
+        });
+        let response = self.client.add_roles(request).await?;
+        WriteResult::try_from(response.into_inner())
+    }
+
+    /// Add one or more platforms to the database in a single transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - add::Options instance, encapsulating the platforms, author and comment
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - WriteResult
+    /// - Err - client::Error
+    pub async fn add_platforms(&mut self, options: add::Options) -> Result<WriteResult> {
+        let add::Options {
+            names,
+            author,
+            comment,
+        } = options;
+        let request = tonic::Request::new(PlatformsAddRequest {
+            names,
+            author,
+            comment,
+        });
+        let response = self.client.add_platforms(request).await?;
+        WriteResult::try_from(response.into_inner())
+    }
+    /// Pin a distribution at every combination of the supplied coords, in a
+    /// single transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - add_versionpins::Options instance, encapsulating the
+    ///   distribution, coords, author and comment
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - VersionPinsWriteResult
+    /// - Err - client::Error
+    pub async fn add_version_pins(
+        &mut self,
+        options: add_versionpins::Options,
+    ) -> Result<VersionPinsWriteResult> {
+        let add_versionpins::Options {
+            package,
+            version,
+            levels,
+            roles,
+            platforms,
+            sites,
+            author,
+            comment,
+        } = options;
+        let request = tonic::Request::new(VersionPinsAddRequest {
+            package,
+            version,
+            levels,
+            roles,
+            platforms,
+            sites,
+            author,
+            comment,
+        });
+        let response = self.client.add_version_pins(request).await?;
+        VersionPinsWriteResult::try_from(response.into_inner())
+    }
+
+    /// Update the distribution one or more versionpins resolve to, in a
+    /// single transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - set_versionpins::Options instance, encapsulating the
+    ///   changes, author and comment
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - VersionPinsWriteResult
+    /// - Err - client::Error
+    pub async fn set_version_pins(
+        &mut self,
+        options: set_versionpins::Options,
+    ) -> Result<VersionPinsWriteResult> {
+        let set_versionpins::Options {
+            changes,
+            author,
+            comment,
+        } = options;
+        let request = tonic::Request::new(VersionPinsSetRequest {
+            changes,
+            author,
+            comment,
+        });
+        let response = self.client.set_version_pins(request).await?;
+        VersionPinsWriteResult::try_from(response.into_inner())
+    }
+    /// Append one or more withs to those of a versionpin.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - withs::Options instance, encapsulating the versionpin id,
+    ///   withs, author and comment
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - WithsWriteResult
+    /// - Err - client::Error
+    pub async fn add_withs(&mut self, options: withs::Options) -> Result<WithsWriteResult> {
+        let withs::Options {
+            versionpin_id,
+            withs,
+            author,
+            comment,
+        } = options;
+        let request = tonic::Request::new(WithsAddRequest {
+            versionpin_id: versionpin_id as i64,
+            withs,
+            author,
+            comment,
+        });
+        let response = self.client.add_withs(request).await?;
+        WithsWriteResult::try_from(response.into_inner())
+    }
+    /// Reorder the withs of a versionpin. The withs must name each of the
+    /// versionpin's withs exactly once.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - withs::Options instance, encapsulating the versionpin id,
+    ///   withs, author and comment
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - WithsWriteResult
+    /// - Err - client::Error
+    pub async fn reorder_withs(&mut self, options: withs::Options) -> Result<WithsWriteResult> {
+        let withs::Options {
+            versionpin_id,
+            withs,
+            author,
+            comment,
+        } = options;
+        let request = tonic::Request::new(WithsReorderRequest {
+            versionpin_id: versionpin_id as i64,
+            withs,
+            author,
+            comment,
+        });
+        let response = self.client.reorder_withs(request).await?;
+        WithsWriteResult::try_from(response.into_inner())
+    }
+    /// Remove one or more withs from those of a versionpin.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - withs::Options instance, encapsulating the versionpin id,
+    ///   withs, author and comment
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - WithsWriteResult
+    /// - Err - client::Error
+    pub async fn remove_withs(&mut self, options: withs::Options) -> Result<WithsWriteResult> {
+        let withs::Options {
+            versionpin_id,
+            withs,
+            author,
+            comment,
+        } = options;
+        let request = tonic::Request::new(WithsRemoveRequest {
+            versionpin_id: versionpin_id as i64,
+            withs,
+            author,
+            comment,
+        });
+        let response = self.client.remove_withs(request).await?;
+        WithsWriteResult::try_from(response.into_inner())
+    }
+    /// Delete one or more versionpins, in a single transaction. Versionpins
+    /// which still have withs are not deleted.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - delete::Options instance, encapsulating the versionpin ids,
+    ///   author, comment and whether this is a dry run
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - DeleteResult of FindAllVersionPinsRow
+    /// - Err - client::Error
+    pub async fn delete_version_pins(
+        &mut self,
+        options: delete::Options,
+    ) -> Result<DeleteResult<FindAllVersionPinsRow>> {
+        let delete::Options {
+            ids,
+            author,
+            comment,
+            dry_run,
+        } = options;
+        let request = tonic::Request::new(VersionPinsDeleteRequest {
+            ids: ids.into_iter().map(|id| id as i64).collect(),
+            author,
+            comment,
+            dry_run: Some(dry_run),
+        });
+        let response = self.client.delete_version_pins(request).await?;
+        let VersionPinsDeleteReply {
+            vpins,
+            revision_id,
+            transaction_id,
+        } = response.into_inner();
+        DeleteResult::from_reply(vpins, revision_id, transaction_id, decode_versionpins_row)
+    }
+
+    /// Delete one or more withs of a versionpin, by id, in a single transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `versionpin_id` - The id of the versionpin whose withs are deleted
+    /// * `options` - delete::Options instance, encapsulating the with ids,
+    ///   author, comment and whether this is a dry run
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - DeleteResult of FindVersionPinWithsRow
+    /// - Err - client::Error
+    pub async fn delete_withs(
+        &mut self,
+        versionpin_id: IdType,
+        options: delete::Options,
+    ) -> Result<DeleteResult<FindVersionPinWithsRow>> {
+        let delete::Options {
+            ids,
+            author,
+            comment,
+            dry_run,
+        } = options;
+        let request = tonic::Request::new(WithsDeleteRequest {
+            versionpin_id: versionpin_id as i64,
+            ids: ids.into_iter().map(|id| id as i64).collect(),
+            author,
+            comment,
+            dry_run: Some(dry_run),
+        });
+        let response = self.client.delete_withs(request).await?;
+        let WithsDeleteReply {
+            withs,
+            revision_id,
+            transaction_id,
+        } = response.into_inner();
+        DeleteResult::from_reply(
+            withs,
+            revision_id,
+            transaction_id,
+            decode_versionpin_withs_row,
+        )
+    }
+
+    /// Delete one or more pkgcoords, in a single transaction. Pkgcoords
+    /// which are referenced by versionpins are not deleted.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - delete::Options instance, encapsulating the pkgcoord ids,
+    ///   author, comment and whether this is a dry run
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - DeleteResult of FindAllPkgCoordsRow
+    /// - Err - client::Error
+    pub async fn delete_pkgcoords(
+        &mut self,
+        options: delete::Options,
+    ) -> Result<DeleteResult<FindAllPkgCoordsRow>> {
+        let delete::Options {
+            ids,
+            author,
+            comment,
+            dry_run,
+        } = options;
+        let request = tonic::Request::new(PkgCoordsDeleteRequest {
+            ids: ids.into_iter().map(|id| id as i64).collect(),
+            author,
+            comment,
+            dry_run: Some(dry_run),
+        });
+        let response = self.client.delete_pkg_coords(request).await?;
+        let PkgCoordsDeleteReply {
+            pkgcoords,
+            revision_id,
+            transaction_id,
+        } = response.into_inner();
+        DeleteResult::from_reply(pkgcoords, revision_id, transaction_id, decode_pkgcoords_row)
+    }
+
+    /// Delete one or more distributions, in a single transaction. Distributions
+    /// which are referenced by versionpins are not deleted.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - delete::Options instance, encapsulating the distribution ids,
+    ///   author, comment and whether this is a dry run
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - DeleteResult of FindAllDistributionsRow
+    /// - Err - client::Error
+    pub async fn delete_distributions(
+        &mut self,
+        options: delete::Options,
+    ) -> Result<DeleteResult<FindAllDistributionsRow>> {
+        let delete::Options {
+            ids,
+            author,
+            comment,
+            dry_run,
+        } = options;
+        let request = tonic::Request::new(DistributionsDeleteRequest {
+            ids: ids.into_iter().map(|id| id as i64).collect(),
+            author,
+            comment,
+            dry_run: Some(dry_run),
+        });
+        let response = self.client.delete_distributions(request).await?;
+        let DistributionsDeleteReply {
+            distributions,
+            revision_id,
+            transaction_id,
+        } = response.into_inner();
+        DeleteResult::from_reply(
+            distributions,
+            revision_id,
+            transaction_id,
+            decode_distributions_row,
+        )
+    }
+    /// Apply an ordered list of ops in a single transaction, recording a
+    /// single revision. If any op fails, none are applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - changeset::Options instance, encapsulating the ops,
+    ///   author and comment
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - ChangesetResult
+    /// - Err - client::Error
+    pub async fn apply_changeset(
+        &mut self,
+        options: changeset::Options,
+    ) -> Result<ChangesetResult> {
+        let changeset::Options {
+            ops,
+            author,
+            comment,
+        } = options;
+        let request = tonic::Request::new(ChangesetRequest {
+            ops,
+            author,
+            comment,
+        });
+        let response = self.client.apply_changeset(request).await?;
+        ChangesetResult::try_from(response.into_inner())
+    }
+    /// Export the versionpins which apply at, or beneath, the supplied coords
+    /// as packages.xml, writing the document to `writer` as it is streamed
+    /// from the server.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - export_packages_xml::Options instance, encapsulating the
+    ///   show and coords
+    /// * `writer` - The destination of the document
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - The number of bytes written
+    /// - Err - client::Error
+    pub async fn export_packages_xml<W>(
+        &mut self,
+        options: export_packages_xml::Options,
+        writer: &mut W,
+    ) -> Result<u64>
+    where
+        W: Write,
+    {
+        let export_packages_xml::Options {
+            show,
+            level,
+            role,
+            platform,
+            site,
+        } = options;
+        let request = tonic::Request::new(PackagesXmlRequest {
+            show,
+            level,
+            role,
+            platform,
+            site,
+        });
+        let mut stream = self.client.export_packages_xml(request).await?.into_inner();
+        let mut written = 0;
+        while let Some(PackagesXmlChunk { data }) = stream.message().await? {
+            writer.write_all(&data).context(Output {
+                target: "packages.xml",
+            })?;
+            written += data.len() as u64;
+        }
+        writer.flush().context(Output {
+            target: "packages.xml",
+        })?;
+        Ok(written)
+    }
+
+    /// Check the health of the server's packybara service
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - ServingStatus
+    /// - Err - client::Error
+    pub async fn check_health(&mut self) -> Result<ServingStatus> {
+        let request = tonic::Request::new(HealthCheckRequest {
+            service: SERVICE_NAME.to_string(),
+        });
+        let response = self.health.check(request).await?;
+        let status =
+            ServingStatus::from_i32(response.into_inner().status).unwrap_or(ServingStatus::Unknown);
+        Ok(status)
+    }
+}
+
+pub mod get_versionpin {
+    /// Encapsulate the query parameters
+    pub struct Options {
+        pub package: String,
+        pub level: Option<String>,
+        pub role: Option<String>,
+        pub platform: Option<String>,
+        pub site: Option<String>,
+    }
+
+    impl Options {
+        /// New up an instance of VetVersionPinOptions given a package name
+        ///
+        /// # Arguments
+        ///
+        /// * `package` - the name of the package
+        ///
+        /// # Returns
+        ///
+        /// * GetVersionPinOptions instance
+        pub fn new<I>(package: I) -> Self
+        where
+            I: Into<String>,
+        {
+            Self {
+                package: package.into(),
+                level: None,
+                role: None,
+                platform: None,
+                site: None,
+            }
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped level,
+        /// set level and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `level` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn level_opt<I>(mut self, level: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.level = level.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped role,
+        /// set role and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `role` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn role_opt<I>(mut self, role: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.role = role.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped platform,
+        /// set platform and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `platform` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn platform_opt<I>(mut self, platform: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.platform = platform.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped site,
+        /// set site and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `site` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn site_opt<I>(mut self, site: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.site = site.map(|x| x.into());
+            self
+        }
+    }
+}
+
+pub mod get_versionpins {
+    use crate::{LtreeSearchMode, OrderDirection, SearchAttribute, VersionPinsQueryRequest};
+
+    /// Encapsulate the query parameters
+    pub struct Options {
+        pub package: Option<String>,
+        pub version: Option<String>,
+        pub level: Option<String>,
+        pub role: Option<String>,
+        pub platform: Option<String>,
+        pub site: Option<String>,
+        pub isolate_facility: Option<bool>,
+        pub search_mode: Option<LtreeSearchMode>,
+        pub order_by: Option<Vec<SearchAttribute>>,
+        pub order_direction: Option<OrderDirection>,
+        pub full_withs: Option<bool>,
+        pub limit: Option<u32>,
+    }
+
+    impl Options {
+        /// New up an instance of VetVersionPinOptions given a package name
+        ///
+        /// # Arguments
+        ///
+        /// * `package` - the name of the package
+        ///
+        /// # Returns
+        ///
+        /// * GetVersionPinOptions instance
+        pub fn new() -> Self {
+            Self {
+                package: None,
+                version: None,
+                level: None,
+                role: None,
+                platform: None,
+                site: None,
+                isolate_facility: None,
+                search_mode: None,
+                order_by: None,
+                order_direction: None,
+                full_withs: None,
+                limit: None,
+            }
+        }
+        pub fn package_opt<I>(mut self, package: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.package = package.map(|x| x.into());
+            self
+        }
+
+        pub fn version_opt<I>(mut self, version: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.version = version.map(|x| x.into());
+            self
+        }
+        /// Given a mutable instance of Self and an Option wrapped level,
+        /// set level and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `level` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn level_opt<I>(mut self, level: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.level = level.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped role,
+        /// set role and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `role` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn role_opt<I>(mut self, role: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.role = role.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped platform,
+        /// set platform and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `platform` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn platform_opt<I>(mut self, platform: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.platform = platform.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped site,
+        /// set site and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `site` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn site_opt<I>(mut self, site: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.site = site.map(|x| x.into());
+            self
+        }
+
+        pub fn isolate_facility_opt(mut self, isolate: Option<bool>) -> Self {
+            self.isolate_facility = isolate;
+            self
+        }
+
+        pub fn search_mode_opt(mut self, mode: Option<LtreeSearchMode>) -> Self {
+            self.search_mode = mode;
+            self
+        }
+
+        pub fn order_by_opt(mut self, order_by: Option<Vec<SearchAttribute>>) -> Self {
+            self.order_by = order_by;
+            self
+        }
+
+        pub fn order_direction_opt(mut self, order_dir: Option<OrderDirection>) -> Self {
+            self.order_direction = order_dir;
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped bool,
+        /// request that the withs of each versionpin be returned as the
+        /// distributions they resolve to, rather than their names.
+        pub fn full_withs_opt(mut self, full_withs: Option<bool>) -> Self {
+            self.full_withs = full_withs;
+            self
+        }
+
+        pub fn limit_opt(mut self, limit: Option<u32>) -> Self {
+            self.limit = limit;
+            self
+        }
+
+        /// Convert the options into the request shared by get_version_pins
+        /// and stream_version_pins
+        pub(crate) fn into_request(self) -> VersionPinsQueryRequest {
+            let Options {
+                package,
+                version,
+                level,
+                role,
+                platform,
+                site,
+                isolate_facility,
+                search_mode,
+                order_by,
+                order_direction,
+                full_withs,
+                limit,
+            } = self;
+            VersionPinsQueryRequest {
+                package,
+                version,
+                level,
+                role,
+                platform,
+                site,
+                isolate_facility,
+                full_withs,
+                limit,
+                page_size: None,
+                page_token: None,
+                search_mode: search_mode.map(|mode| mode as i32),
+                order_by: order_by
+                    .into_iter()
+                    .flatten()
+                    .map(|attr| attr as i32)
+                    .collect(),
+                order_direction: order_direction.map(|dir| dir as i32),
+            }
+        }
+    }
+}
+pub mod get_roles {
+    /// Encapsulate the query parameters
+    pub struct Options {
+        pub role: Option<String>,
+        pub category: Option<String>,
+        pub order_by: Option<String>,
+    }
+
+    impl Options {
+        /// New up an instance of get_roles::Options
+        pub fn new() -> Self {
+            Self {
+                role: None,
+                category: None,
+                order_by: None,
+            }
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped role,
+        /// set role and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `role` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn role_opt<I>(mut self, role: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.role = role.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped category
+        /// (role, subrole, or any), set category and return Self, following
+        /// the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `category` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn category_opt<I>(mut self, category: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.category = category.map(|x| x.into());
+            self
+        }
+
+        pub fn order_by_opt(mut self, order_by: Option<String>) -> Self {
+            self.order_by = order_by;
+            self
+        }
+    }
+}
+
+pub mod get_platforms {
+    /// Encapsulate the query parameters
+    pub struct Options {
+        pub platform: Option<String>,
+        pub order_by: Option<String>,
+    }
+
+    impl Options {
+        /// New up an instance of get_platforms::Options
+        pub fn new() -> Self {
+            Self {
+                platform: None,
+                order_by: None,
+            }
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped platform,
+        /// set platform and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `platform` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn platform_opt<I>(mut self, platform: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.platform = platform.map(|x| x.into());
+            self
+        }
+
+        pub fn order_by_opt(mut self, order_by: Option<String>) -> Self {
+            self.order_by = order_by;
+            self
+        }
+    }
+}
+
+pub mod get_sites {
+    /// Encapsulate the query parameters
+    pub struct Options {
+        pub site: Option<String>,
+    }
+
+    impl Options {
+        /// New up an instance of get_sites::Options
+        pub fn new() -> Self {
+            Self { site: None }
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped site,
+        /// set site and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `site` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn site_opt<I>(mut self, site: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.site = site.map(|x| x.into());
+            self
+        }
+    }
+}
+
+pub mod get_levels {
+    /// Encapsulate the query parameters
+    pub struct Options {
+        pub level: Option<String>,
+        pub show: Option<String>,
+        pub depth: Option<u8>,
+        pub order_by: Option<String>,
+    }
+
+    impl Options {
+        /// New up an instance of get_levels::Options
+        pub fn new() -> Self {
+            Self {
+                level: None,
+                show: None,
+                depth: None,
+                order_by: None,
+            }
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped level,
+        /// set level and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `level` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn level_opt<I>(mut self, level: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.level = level.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped show,
+        /// set show and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `show` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn show_opt<I>(mut self, show: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.show = show.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped depth
+        /// (1 for show, 2 for sequence, 3 for shot), set depth and return Self,
+        /// following the common builder pattern.
+        pub fn depth_opt(mut self, depth: Option<u8>) -> Self {
+            self.depth = depth;
+            self
+        }
+
+        pub fn order_by_opt(mut self, order_by: Option<String>) -> Self {
+            self.order_by = order_by;
+            self
+        }
+    }
+}
+pub mod get_distributions {
+    use crate::OrderDirection;
+
+    /// Encapsulate the query parameters
+    pub struct Options {
+        pub package: Option<String>,
+        pub version: Option<String>,
+        pub order_direction: Option<OrderDirection>,
+    }
+
+    impl Options {
+        /// New up an instance of get_distributions::Options
+        pub fn new() -> Self {
+            Self {
+                package: None,
+                version: None,
+                order_direction: None,
+            }
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped package,
+        /// set package and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `package` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn package_opt<I>(mut self, package: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.package = package.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped version,
+        /// set version and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `version` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn version_opt<I>(mut self, version: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.version = version.map(|x| x.into());
+            self
+        }
+
+        pub fn order_direction_opt(mut self, order_dir: Option<OrderDirection>) -> Self {
+            self.order_direction = order_dir;
+            self
+        }
+    }
+}
+pub mod get_pkgcoords {
+    use crate::LtreeSearchMode;
+
+    /// Encapsulate the query parameters
+    pub struct Options {
+        pub package: Option<String>,
+        pub level: Option<String>,
+        pub role: Option<String>,
+        pub platform: Option<String>,
+        pub site: Option<String>,
+        pub search_mode: Option<LtreeSearchMode>,
+        pub order_by: Option<String>,
+    }
+
+    impl Options {
+        /// New up an instance of get_pkgcoords::Options
+        pub fn new() -> Self {
+            Self {
+                package: None,
+                level: None,
+                role: None,
+                platform: None,
+                site: None,
+                search_mode: None,
+                order_by: None,
+            }
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped package,
+        /// set package and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `package` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn package_opt<I>(mut self, package: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.package = package.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped level,
+        /// set level and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `level` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn level_opt<I>(mut self, level: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.level = level.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped role,
+        /// set role and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `role` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn role_opt<I>(mut self, role: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.role = role.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped platform,
+        /// set platform and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `platform` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn platform_opt<I>(mut self, platform: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.platform = platform.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped site,
+        /// set site and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `site` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn site_opt<I>(mut self, site: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.site = site.map(|x| x.into());
+            self
+        }
+
+        pub fn search_mode_opt(mut self, mode: Option<LtreeSearchMode>) -> Self {
+            self.search_mode = mode;
+            self
+        }
+
+        pub fn order_by_opt(mut self, order_by: Option<String>) -> Self {
+            self.order_by = order_by;
+            self
+        }
+    }
+}
+
+pub mod get_pins {
+    use crate::{LtreeSearchMode, SearchAttribute};
+    use packybara::types::IdType;
+
+    /// Encapsulate the query parameters
+    pub struct Options {
+        pub level: Option<String>,
+        pub role: Option<String>,
+        pub platform: Option<String>,
+        pub site: Option<String>,
+        pub search_mode: Option<LtreeSearchMode>,
+        pub order_by: Option<Vec<SearchAttribute>>,
+        pub limit: Option<IdType>,
+    }
+
+    impl Options {
+        /// New up an instance of get_pins::Options
+        pub fn new() -> Self {
+            Self {
+                level: None,
+                role: None,
+                platform: None,
+                site: None,
+                search_mode: None,
+                order_by: None,
+                limit: None,
+            }
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped level,
+        /// set level and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `level` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn level_opt<I>(mut self, level: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.level = level.map(|x| x.into());
+            self
+        }
 
-        Ok(results)
+        /// Given a mutable instance of Self and an Option wrapped role,
+        /// set role and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `role` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn role_opt<I>(mut self, role: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.role = role.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped platform,
+        /// set platform and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `platform` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn platform_opt<I>(mut self, platform: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.platform = platform.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped site,
+        /// set site and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `site` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn site_opt<I>(mut self, site: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.site = site.map(|x| x.into());
+            self
+        }
+
+        pub fn search_mode_opt(mut self, mode: Option<LtreeSearchMode>) -> Self {
+            self.search_mode = mode;
+            self
+        }
+
+        pub fn order_by_opt(mut self, order_by: Option<Vec<SearchAttribute>>) -> Self {
+            self.order_by = order_by;
+            self
+        }
+
+        pub fn limit_opt(mut self, limit: Option<IdType>) -> Self {
+            self.limit = limit;
+            self
+        }
     }
 }
-
-pub mod get_versionpin {
+pub mod get_withs {
     /// Encapsulate the query parameters
     pub struct Options {
         pub package: String,
@@ -165,7 +2528,7 @@ pub mod get_versionpin {
     }
 
     impl Options {
-        /// New up an instance of VetVersionPinOptions given a package name
+        /// New up an instance of get_withs::Options given a package name
         ///
         /// # Arguments
         ///
@@ -173,7 +2536,7 @@ pub mod get_versionpin {
         ///
         /// # Returns
         ///
-        /// * GetVersionPinOptions instance
+        /// * get_withs::Options instance
         pub fn new<I>(package: I) -> Self
         where
             I: Into<String>,
@@ -192,219 +2555,872 @@ pub mod get_versionpin {
         ///
         /// # Arguments
         ///
-        /// * `level` - An option wrapped type that implements Into<String>
+        /// * `level` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn level_opt<I>(mut self, level: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.level = level.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped role,
+        /// set role and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `role` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn role_opt<I>(mut self, role: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.role = role.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped platform,
+        /// set platform and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `platform` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn platform_opt<I>(mut self, platform: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.platform = platform.map(|x| x.into());
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped site,
+        /// set site and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `site` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn site_opt<I>(mut self, site: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.site = site.map(|x| x.into());
+            self
+        }
+    }
+}
+pub mod get_revisions {
+    use crate::OrderDirection;
+    use packybara::types::{IdType, LongIdType};
+
+    /// Encapsulate the query parameters
+    pub struct Options {
+        pub id: Option<IdType>,
+        pub transaction_id: Option<LongIdType>,
+        pub author: Option<String>,
+        pub order_by: Option<String>,
+        pub order_direction: Option<OrderDirection>,
+        pub limit: Option<IdType>,
+    }
+
+    impl Options {
+        /// New up an instance of get_revisions::Options
+        pub fn new() -> Self {
+            Self {
+                id: None,
+                transaction_id: None,
+                author: None,
+                order_by: None,
+                order_direction: None,
+                limit: None,
+            }
+        }
+
+        pub fn id_opt(mut self, id: Option<IdType>) -> Self {
+            self.id = id;
+            self
+        }
+
+        pub fn transaction_id_opt(mut self, transaction_id: Option<LongIdType>) -> Self {
+            self.transaction_id = transaction_id;
+            self
+        }
+
+        /// Given a mutable instance of Self and an Option wrapped author,
+        /// set author and return Self, following the common builder pattern.
+        ///
+        /// # Arguments
+        ///
+        /// * `author` - An option wrapped type that implements Into<String>
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn author_opt<I>(mut self, author: Option<I>) -> Self
+        where
+            I: Into<String>,
+        {
+            self.author = author.map(|x| x.into());
+            self
+        }
+
+        pub fn order_by_opt(mut self, order_by: Option<String>) -> Self {
+            self.order_by = order_by;
+            self
+        }
+
+        pub fn order_direction_opt(mut self, order_dir: Option<OrderDirection>) -> Self {
+            self.order_direction = order_dir;
+            self
+        }
+
+        pub fn limit_opt(mut self, limit: Option<IdType>) -> Self {
+            self.limit = limit;
+            self
+        }
+    }
+}
+pub mod add {
+    /// Encapsulate the parameters shared by requests which add named entities
+    pub struct Options {
+        pub names: Vec<String>,
+        pub author: String,
+        pub comment: String,
+    }
+
+    impl Options {
+        /// New up an instance of add::Options, given the names of the
+        /// entities to add, along with the author and comment recorded
+        /// in the resulting revision.
+        ///
+        /// # Arguments
+        ///
+        /// * `names` - The names of the entities to add
+        /// * `author` - The author of the change
+        /// * `comment` - A description of the change
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn new<A, C>(names: Vec<String>, author: A, comment: C) -> Self
+        where
+            A: Into<String>,
+            C: Into<String>,
+        {
+            Self {
+                names,
+                author: author.into(),
+                comment: comment.into(),
+            }
+        }
+    }
+}
+
+pub mod add_versionpins {
+    /// Encapsulate the distribution to pin and the coords to pin it at
+    pub struct Options {
+        pub package: String,
+        pub version: String,
+        pub levels: Vec<String>,
+        pub roles: Vec<String>,
+        pub platforms: Vec<String>,
+        pub sites: Vec<String>,
+        pub author: String,
+        pub comment: String,
+    }
+
+    impl Options {
+        /// New up an instance of add_versionpins::Options. Coords which are
+        /// not supplied default to facility, or any, respectively.
+        ///
+        /// # Arguments
+        ///
+        /// * `package` - The name of the package
+        /// * `version` - The version of the distribution to pin
+        /// * `author` - The author of the change
+        /// * `comment` - A description of the change
+        ///
+        /// # Returns
+        ///
+        /// * Self
+        pub fn new<P, V, A, C>(package: P, version: V, author: A, comment: C) -> Self
+        where
+            P: Into<String>,
+            V: Into<String>,
+            A: Into<String>,
+            C: Into<String>,
+        {
+            Self {
+                package: package.into(),
+                version: version.into(),
+                levels: Vec::new(),
+                roles: Vec::new(),
+                platforms: Vec::new(),
+                sites: Vec::new(),
+                author: author.into(),
+                comment: comment.into(),
+            }
+        }
+
+        pub fn levels(mut self, levels: Vec<String>) -> Self {
+            self.levels = levels;
+            self
+        }
+
+        pub fn roles(mut self, roles: Vec<String>) -> Self {
+            self.roles = roles;
+            self
+        }
+
+        pub fn platforms(mut self, platforms: Vec<String>) -> Self {
+            self.platforms = platforms;
+            self
+        }
+
+        pub fn sites(mut self, sites: Vec<String>) -> Self {
+            self.sites = sites;
+            self
+        }
+    }
+}
+
+pub mod set_versionpins {
+    use crate::{Coords, VersionPinChange};
+    use packybara::types::IdType;
+
+    /// Encapsulate the changes to make, along with the author and comment
+    /// recorded in the resulting revision
+    pub struct Options {
+        pub changes: Vec<VersionPinChange>,
+        pub author: String,
+        pub comment: String,
+    }
+
+    impl Options {
+        /// New up an instance of set_versionpins::Options without any changes
+        pub fn new<A, C>(author: A, comment: C) -> Self
+        where
+            A: Into<String>,
+            C: Into<String>,
+        {
+            Self {
+                changes: Vec::new(),
+                author: author.into(),
+                comment: comment.into(),
+            }
+        }
+
+        /// Resolve the versionpin with the supplied id to the distribution
+        /// with the supplied id.
+        ///
+        /// # Arguments
+        ///
+        /// * `versionpin_id` - The id of the versionpin to update
+        /// * `distribution_id` - The id of the distribution to resolve to
         ///
         /// # Returns
         ///
         /// * Self
-        pub fn level_opt<I>(mut self, level: Option<I>) -> Self
-        where
-            I: Into<String>,
-        {
-            self.level = level.map(|x| x.into());
+        pub fn change_by_id(mut self, versionpin_id: IdType, distribution_id: IdType) -> Self {
+            self.changes.push(VersionPinChange {
+                versionpin_id: Some(versionpin_id as i64),
+                package: None,
+                coords: None,
+                distribution_id: Some(distribution_id as i64),
+                version: None,
+            });
             self
         }
 
-        /// Given a mutable instance of Self and an Option wrapped role,
-        /// set role and return Self, following the common builder pattern.
+        /// Resolve the versionpin of a package at exactly the supplied coords
+        /// to the supplied version of the package.
         ///
         /// # Arguments
         ///
-        /// * `role` - An option wrapped type that implements Into<String>
+        /// * `package` - The name of the package
+        /// * `coords` - The level, role, platform and site of the versionpin
+        /// * `version` - The version of the distribution to resolve to
         ///
         /// # Returns
         ///
         /// * Self
-        pub fn role_opt<I>(mut self, role: Option<I>) -> Self
+        pub fn change_by_coords<P, V>(mut self, package: P, coords: Coords, version: V) -> Self
         where
-            I: Into<String>,
+            P: Into<String>,
+            V: Into<String>,
         {
-            self.role = role.map(|x| x.into());
+            self.changes.push(VersionPinChange {
+                versionpin_id: None,
+                package: Some(package.into()),
+                coords: Some(coords),
+                distribution_id: None,
+                version: Some(version.into()),
+            });
             self
         }
+    }
+}
 
-        /// Given a mutable instance of Self and an Option wrapped platform,
-        /// set platform and return Self, following the common builder pattern.
+pub mod withs {
+    use packybara::types::IdType;
+
+    /// Encapsulate the parameters of requests which change the withs of a versionpin
+    pub struct Options {
+        pub versionpin_id: IdType,
+        pub withs: Vec<String>,
+        pub author: String,
+        pub comment: String,
+    }
+
+    impl Options {
+        /// New up an instance of withs::Options
         ///
         /// # Arguments
         ///
-        /// * `platform` - An option wrapped type that implements Into<String>
+        /// * `versionpin_id` - The id of the versionpin whose withs are changed
+        /// * `withs` - The names of the with packages
+        /// * `author` - The author of the change
+        /// * `comment` - A description of the change
         ///
         /// # Returns
         ///
         /// * Self
-        pub fn platform_opt<I>(mut self, platform: Option<I>) -> Self
+        pub fn new<A, C>(versionpin_id: IdType, withs: Vec<String>, author: A, comment: C) -> Self
         where
-            I: Into<String>,
+            A: Into<String>,
+            C: Into<String>,
         {
-            self.platform = platform.map(|x| x.into());
-            self
+            Self {
+                versionpin_id,
+                withs,
+                author: author.into(),
+                comment: comment.into(),
+            }
         }
+    }
+}
 
-        /// Given a mutable instance of Self and an Option wrapped site,
-        /// set site and return Self, following the common builder pattern.
+pub mod delete {
+    use packybara::types::IdType;
+
+    /// Encapsulate the parameters of requests which delete rows by id
+    pub struct Options {
+        pub ids: Vec<IdType>,
+        pub author: String,
+        pub comment: String,
+        pub dry_run: bool,
+    }
+
+    impl Options {
+        /// New up an instance of delete::Options
         ///
         /// # Arguments
         ///
-        /// * `site` - An option wrapped type that implements Into<String>
+        /// * `ids` - The ids of the rows to delete
+        /// * `author` - The author of the change
+        /// * `comment` - A description of the change
         ///
         /// # Returns
         ///
         /// * Self
-        pub fn site_opt<I>(mut self, site: Option<I>) -> Self
+        pub fn new<A, C>(ids: Vec<IdType>, author: A, comment: C) -> Self
         where
-            I: Into<String>,
+            A: Into<String>,
+            C: Into<String>,
         {
-            self.site = site.map(|x| x.into());
+            Self {
+                ids,
+                author: author.into(),
+                comment: comment.into(),
+                dry_run: false,
+            }
+        }
+
+        /// Report the rows which would be deleted, without deleting them
+        pub fn dry_run(mut self, dry_run: bool) -> Self {
+            self.dry_run = dry_run;
             self
         }
     }
 }
 
-pub mod get_versionpins {
-    /// Encapsulate the query parameters
+pub mod changeset {
+    use crate::{
+        changeset_op::Op, ChangesetOp, Coords, IdsOp, NamesOp, VersionPinChange, VersionPinsAddOp,
+        WithsDeleteOp, WithsOp,
+    };
+    use packybara::types::IdType;
+
+    /// Encapsulate an ordered list of ops, along with the author and comment
+    /// recorded in the resulting revision
     pub struct Options {
-        pub package: Option<String>,
-        pub version: Option<String>,
+        pub ops: Vec<ChangesetOp>,
+        pub author: String,
+        pub comment: String,
+    }
+
+    impl Options {
+        /// New up an instance of changeset::Options without any ops
+        pub fn new<A, C>(author: A, comment: C) -> Self
+        where
+            A: Into<String>,
+            C: Into<String>,
+        {
+            Self {
+                ops: Vec::new(),
+                author: author.into(),
+                comment: comment.into(),
+            }
+        }
+
+        fn op(mut self, op: Op) -> Self {
+            self.ops.push(ChangesetOp { op: Some(op) });
+            self
+        }
+
+        pub fn add_packages(self, names: Vec<String>) -> Self {
+            self.op(Op::AddPackages(NamesOp { names }))
+        }
+
+        pub fn add_levels(self, names: Vec<String>) -> Self {
+            self.op(Op::AddLevels(NamesOp { names }))
+        }
+
+        pub fn add_roles(self, names: Vec<String>) -> Self {
+            self.op(Op::AddRoles(NamesOp { names }))
+        }
+
+        pub fn add_platforms(self, names: Vec<String>) -> Self {
+            self.op(Op::AddPlatforms(NamesOp { names }))
+        }
+
+        /// Pin a distribution at every combination of the supplied coords.
+        /// Empty coords default to facility, or any, respectively.
+        pub fn add_versionpins(self, versionpins: VersionPinsAddOp) -> Self {
+            self.op(Op::AddVersionpins(versionpins))
+        }
+
+        /// Resolve the versionpin with the supplied id to the distribution
+        /// with the supplied id.
+        pub fn set_versionpin_by_id(self, versionpin_id: IdType, distribution_id: IdType) -> Self {
+            self.op(Op::SetVersionpin(VersionPinChange {
+                versionpin_id: Some(versionpin_id as i64),
+                package: None,
+                coords: None,
+                distribution_id: Some(distribution_id as i64),
+                version: None,
+            }))
+        }
+
+        /// Resolve the versionpin of a package at exactly the supplied coords
+        /// to the supplied version of the package.
+        pub fn set_versionpin_by_coords<P, V>(self, package: P, coords: Coords, version: V) -> Self
+        where
+            P: Into<String>,
+            V: Into<String>,
+        {
+            self.op(Op::SetVersionpin(VersionPinChange {
+                versionpin_id: None,
+                package: Some(package.into()),
+                coords: Some(coords),
+                distribution_id: None,
+                version: Some(version.into()),
+            }))
+        }
+
+        /// Append the supplied withs to those of a versionpin
+        pub fn add_withs(self, versionpin_id: IdType, withs: Vec<String>) -> Self {
+            self.op(Op::AddWiths(to_withs_op(versionpin_id, withs)))
+        }
+
+        /// Reorder the withs of a versionpin, which must each be named once
+        pub fn reorder_withs(self, versionpin_id: IdType, withs: Vec<String>) -> Self {
+            self.op(Op::ReorderWiths(to_withs_op(versionpin_id, withs)))
+        }
+
+        /// Remove the supplied withs from those of a versionpin
+        pub fn remove_withs(self, versionpin_id: IdType, withs: Vec<String>) -> Self {
+            self.op(Op::RemoveWiths(to_withs_op(versionpin_id, withs)))
+        }
+
+        pub fn delete_versionpins(self, ids: Vec<IdType>) -> Self {
+            self.op(Op::DeleteVersionpins(to_ids_op(ids)))
+        }
+
+        /// Delete the withs of a versionpin with the supplied ids
+        pub fn delete_withs(self, versionpin_id: IdType, ids: Vec<IdType>) -> Self {
+            self.op(Op::DeleteWiths(WithsDeleteOp {
+                versionpin_id: versionpin_id as i64,
+                ids: to_ids_op(ids).ids,
+            }))
+        }
+
+        pub fn delete_pkgcoords(self, ids: Vec<IdType>) -> Self {
+            self.op(Op::DeletePkgcoords(to_ids_op(ids)))
+        }
+
+        pub fn delete_distributions(self, ids: Vec<IdType>) -> Self {
+            self.op(Op::DeleteDistributions(to_ids_op(ids)))
+        }
+    }
+
+    fn to_ids_op(ids: Vec<IdType>) -> IdsOp {
+        IdsOp {
+            ids: ids.into_iter().map(|id| id as i64).collect(),
+        }
+    }
+
+    fn to_withs_op(versionpin_id: IdType, withs: Vec<String>) -> WithsOp {
+        WithsOp {
+            versionpin_id: versionpin_id as i64,
+            withs,
+        }
+    }
+}
+
+pub mod export_packages_xml {
+    /// Encapsulate the show and coords to export
+    pub struct Options {
+        pub show: String,
         pub level: Option<String>,
         pub role: Option<String>,
         pub platform: Option<String>,
         pub site: Option<String>,
-        pub isolate_facility: Option<bool>,
-        pub search_mode: Option<String>,
-        pub order_by: Option<String>,
-        pub order_direction: Option<String>,
     }
 
     impl Options {
-        /// New up an instance of VetVersionPinOptions given a package name
-        ///
-        /// # Arguments
-        ///
-        /// * `package` - the name of the package
-        ///
-        /// # Returns
-        ///
-        /// * GetVersionPinOptions instance
-        pub fn new() -> Self {
+        /// New up an instance of export_packages_xml::Options, given the show
+        /// to export. The level defaults to the show, and the role, platform
+        /// and site to any.
+        pub fn new<I>(show: I) -> Self
+        where
+            I: Into<String>,
+        {
             Self {
-                package: None,
-                version: None,
+                show: show.into(),
                 level: None,
                 role: None,
                 platform: None,
                 site: None,
-                isolate_facility: None,
-                search_mode: None,
-                order_by: None,
-                order_direction: None,
             }
         }
-        pub fn package_opt<I>(mut self, package: Option<I>) -> Self
-        where
-            I: Into<String>,
-        {
-            self.package = package.map(|x| x.into());
+
+        pub fn level_opt(mut self, level: Option<String>) -> Self {
+            self.level = level;
             self
         }
 
-        pub fn version_opt<I>(mut self, version: Option<I>) -> Self
-        where
-            I: Into<String>,
-        {
-            self.version = version.map(|x| x.into());
+        pub fn role_opt(mut self, role: Option<String>) -> Self {
+            self.role = role;
             self
         }
-        /// Given a mutable instance of Self and an Option wrapped level,
-        /// set level and return Self, following the common builder pattern.
-        ///
-        /// # Arguments
-        ///
-        /// * `level` - An option wrapped type that implements Into<String>
-        ///
-        /// # Returns
-        ///
-        /// * Self
-        pub fn level_opt<I>(mut self, level: Option<I>) -> Self
-        where
-            I: Into<String>,
-        {
-            self.level = level.map(|x| x.into());
+
+        pub fn platform_opt(mut self, platform: Option<String>) -> Self {
+            self.platform = platform;
             self
         }
 
-        /// Given a mutable instance of Self and an Option wrapped role,
-        /// set role and return Self, following the common builder pattern.
-        ///
-        /// # Arguments
-        ///
-        /// * `role` - An option wrapped type that implements Into<String>
-        ///
-        /// # Returns
-        ///
-        /// * Self
-        pub fn role_opt<I>(mut self, role: Option<I>) -> Self
-        where
-            I: Into<String>,
-        {
-            self.role = role.map(|x| x.into());
+        pub fn site_opt(mut self, site: Option<String>) -> Self {
+            self.site = site;
             self
         }
+    }
+}
 
-        /// Given a mutable instance of Self and an Option wrapped platform,
-        /// set platform and return Self, following the common builder pattern.
-        ///
-        /// # Arguments
-        ///
-        /// * `platform` - An option wrapped type that implements Into<String>
-        ///
-        /// # Returns
-        ///
-        /// * Self
-        pub fn platform_opt<I>(mut self, platform: Option<I>) -> Self
-        where
-            I: Into<String>,
-        {
-            self.platform = platform.map(|x| x.into());
-            self
+pub mod tls {
+    use super::{ReadPem, Result};
+    use snafu::ResultExt;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use tonic::transport::{Certificate, ClientTlsConfig, Identity};
+
+    /// Encapsulate the tls parameters used to connect to an https url
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct Options {
+        pub ca: Option<PathBuf>,
+        pub cert: Option<PathBuf>,
+        pub key: Option<PathBuf>,
+        pub domain: Option<String>,
+    }
+
+    impl Options {
+        /// New up an Options instance, trusting the default roots and
+        /// presenting no client certificate.
+        pub fn new() -> Self {
+            Self::default()
         }
 
-        /// Given a mutable instance of Self and an Option wrapped site,
-        /// set site and return Self, following the common builder pattern.
-        ///
-        /// # Arguments
-        ///
-        /// * `site` - An option wrapped type that implements Into<String>
-        ///
-        /// # Returns
-        ///
-        /// * Self
-        pub fn site_opt<I>(mut self, site: Option<I>) -> Self
-        where
-            I: Into<String>,
-        {
-            self.site = site.map(|x| x.into());
+        /// Given a mutable instance of Self and the path to a PEM encoded CA
+        /// bundle, trust the server certificates it has signed.
+        pub fn ca<I: Into<PathBuf>>(mut self, ca: I) -> Self {
+            self.ca = Some(ca.into());
             self
         }
 
-        pub fn isolate_facility_opt(mut self, isolate: Option<bool>) -> Self {
-            self.isolate_facility = isolate;
+        /// Given a mutable instance of Self and an Option wrapped CA bundle path,
+        /// set the ca and return Self, following the common builder pattern.
+        pub fn ca_opt<I: Into<PathBuf>>(mut self, ca: Option<I>) -> Self {
+            self.ca = ca.map(|x| x.into());
             self
         }
 
-        pub fn search_mode_opt(mut self, mode: Option<String>) -> Self {
-            self.search_mode = mode;
+        /// Given a mutable instance of Self and the paths to a PEM encoded
+        /// certificate chain and private key, present them to servers which
+        /// require client certificates.
+        pub fn identity<C, K>(mut self, cert: C, key: K) -> Self
+        where
+            C: Into<PathBuf>,
+            K: Into<PathBuf>,
+        {
+            self.cert = Some(cert.into());
+            self.key = Some(key.into());
             self
         }
 
-        pub fn order_by_opt(mut self, order_by: Option<String>) -> Self {
-            self.order_by = order_by;
+        /// Given a mutable instance of Self and a domain name, verify the
+        /// server's certificate against it rather than the host of the url.
+        pub fn domain<I: Into<String>>(mut self, domain: I) -> Self {
+            self.domain = Some(domain.into());
             self
         }
 
-        pub fn order_direction_opt(mut self, order_dir: Option<String>) -> Self {
-            self.order_direction = order_dir;
+        /// Given a mutable instance of Self and an Option wrapped domain name,
+        /// set the domain and return Self, following the common builder pattern.
+        pub fn domain_opt<I: Into<String>>(mut self, domain: Option<I>) -> Self {
+            self.domain = domain.map(|x| x.into());
             self
         }
+
+        /// Load the CA bundle and client identity into the tls config handed
+        /// to the channel. A client identity is only presented when both a
+        /// cert and key have been supplied.
+        pub(crate) fn client_tls_config(&self) -> Result<ClientTlsConfig> {
+            let mut tls = ClientTlsConfig::with_rustls();
+            if let Some(ref ca) = self.ca {
+                tls.ca_certificate(Certificate::from_pem(read_pem("ca", ca)?));
+            }
+            if let (Some(ref cert), Some(ref key)) = (&self.cert, &self.key) {
+                tls.identity(Identity::from_pem(
+                    read_pem("cert", cert)?,
+                    read_pem("key", key)?,
+                ));
+            }
+            if let Some(ref domain) = self.domain {
+                tls.domain_name(domain.as_str());
+            }
+            Ok(tls)
+        }
+    }
+
+    // Read a PEM encoded file named by the options
+    fn read_pem(name: &str, path: &Path) -> Result<Vec<u8>> {
+        fs::read(path).context(ReadPem { name, path })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{changeset_op, LtreeSearchMode, OrderDirection, SearchAttribute, WithsDeleteOp};
+
+    #[test]
+    fn can_map_status_to_error() {
+        let err = Error::from(Status::new(Code::NotFound, "no pin"));
+        assert!(matches!(err, Error::NotFound { .. }));
+        assert_eq!(err.code(), Some(Code::NotFound));
+        let err = Error::from(Status::new(Code::Unavailable, "db down"));
+        assert!(matches!(err, Error::Unavailable { .. }));
+        let err = Error::from(Status::new(Code::Internal, "oops"));
+        assert!(matches!(err, Error::Rpc { .. }));
+        assert_eq!(err.code(), Some(Code::Internal));
+    }
+
+    #[test]
+    fn cannot_decode_out_of_range_id() {
+        assert_eq!(decode_id("versionpin_id", 42).unwrap(), 42);
+        let err = decode_id("versionpin_id", i64::max_value()).unwrap_err();
+        assert!(matches!(err, Error::Decode { .. }));
+    }
+
+    #[test]
+    fn can_decode_timestamp() {
+        let timestamp = prost_types::Timestamp {
+            seconds: 1_580_000_000,
+            nanos: 5,
+        };
+        let datetime = decode_timestamp("datetime", &timestamp).unwrap();
+        assert_eq!(datetime.timestamp(), 1_580_000_000);
+        assert_eq!(datetime.timestamp_subsec_nanos(), 5);
+        let timestamp = prost_types::Timestamp {
+            seconds: 0,
+            nanos: -1,
+        };
+        assert!(decode_timestamp("datetime", &timestamp).is_err());
+    }
+
+    #[test]
+    fn can_decode_add_reply() {
+        let result = WriteResult::try_from(AddReply {
+            updates: 2,
+            revision_id: 7,
+            transaction_id: 1234,
+        })
+        .unwrap();
+        assert_eq!(
+            result,
+            WriteResult {
+                updates: 2,
+                revision_id: 7,
+                transaction_id: 1234,
+            }
+        );
+        let err = WriteResult::try_from(AddReply {
+            updates: -1,
+            revision_id: 7,
+            transaction_id: 1234,
+        })
+        .unwrap_err();
+        assert!(matches!(err, Error::Decode { .. }));
+    }
+
+    #[test]
+    fn cannot_decode_unresolved_with() {
+        let with = VersionPinWithsQueryRow {
+            id: 1,
+            versionpin_id: 3,
+            with: "gcc".to_string(),
+            order: 0,
+            resolved: None,
+        };
+        let err = decode_resolved_with(with).unwrap_err();
+        assert!(matches!(err, Error::Decode { .. }));
+    }
+
+    #[test]
+    fn can_build_versionpin_changes() {
+        let coords = Coords {
+            level: "dev01".to_string(),
+            role: "model".to_string(),
+            platform: "any".to_string(),
+            site: "any".to_string(),
+        };
+        let set_versionpins::Options { changes, .. } =
+            set_versionpins::Options::new("jgerber", "update maya")
+                .change_by_id(3, 12)
+                .change_by_coords("maya", coords.clone(), "2020.1.0");
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].versionpin_id, Some(3));
+        assert_eq!(changes[0].distribution_id, Some(12));
+        assert_eq!(changes[1].package.as_deref(), Some("maya"));
+        assert_eq!(changes[1].coords, Some(coords));
+        assert_eq!(changes[1].version.as_deref(), Some("2020.1.0"));
+    }
+
+    #[test]
+    fn can_decode_dry_run_delete_reply() {
+        let distributions = vec![DistributionsQueryRow {
+            id: 4,
+            package: "maya".to_string(),
+            version: "2020.1.0".to_string(),
+        }];
+        let result =
+            DeleteResult::from_reply(distributions, None, None, decode_distributions_row).unwrap();
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0].id, 4);
+        assert_eq!(result.revision_id, None);
+        assert_eq!(result.transaction_id, None);
+    }
+
+    #[test]
+    fn can_build_delete_withs_op() {
+        let changeset::Options { ops, .. } =
+            changeset::Options::new("jgerber", "drop withs").delete_withs(3, vec![7, 8]);
+        assert_eq!(
+            ops[0].op,
+            Some(changeset_op::Op::DeleteWiths(WithsDeleteOp {
+                versionpin_id: 3,
+                ids: vec![7, 8],
+            }))
+        );
+    }
+
+    #[test]
+    fn changeset_results_are_ordered_by_index() {
+        let result = ChangesetResult::try_from(ChangesetReply {
+            results: vec![
+                ChangesetOpResult { index: 1, rows: 4 },
+                ChangesetOpResult { index: 0, rows: 2 },
+            ],
+            revision_id: 9,
+            transaction_id: 1234,
+        })
+        .unwrap();
+        assert_eq!(result.rows, vec![2, 4]);
+        assert_eq!(result.revision_id, 9);
+    }
+
+    #[test]
+    fn versionpins_options_carry_limit_and_full_withs() {
+        let request = get_versionpins::Options::new()
+            .full_withs_opt(Some(true))
+            .limit_opt(Some(10))
+            .into_request();
+        assert_eq!(request.full_withs, Some(true));
+        assert_eq!(request.limit, Some(10));
+    }
+
+    #[test]
+    fn versionpins_options_carry_search_enums() {
+        let request = get_versionpins::Options::new()
+            .search_mode_opt(Some(LtreeSearchMode::Exact))
+            .order_by_opt(Some(vec![SearchAttribute::Level, SearchAttribute::Site]))
+            .order_direction_opt(Some(OrderDirection::Desc))
+            .into_request();
+        assert_eq!(request.search_mode, Some(LtreeSearchMode::Exact as i32));
+        assert_eq!(
+            request.order_by,
+            vec![SearchAttribute::Level as i32, SearchAttribute::Site as i32]
+        );
+        assert_eq!(request.order_direction, Some(OrderDirection::Desc as i32));
+    }
+
+    #[test]
+    fn can_load_client_tls_config() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert_path = dir.path().join("client.pem");
+        let key_path = dir.path().join("client.key");
+        std::fs::write(&cert_path, cert.serialize_pem().unwrap()).unwrap();
+        std::fs::write(&key_path, cert.serialize_private_key_pem()).unwrap();
+        let options = tls::Options::new()
+            .ca(&cert_path)
+            .identity(&cert_path, &key_path)
+            .domain("localhost");
+        assert!(options.client_tls_config().is_ok());
+        let options = options.ca(dir.path().join("missing.pem"));
+        assert!(matches!(
+            options.client_tls_config(),
+            Err(Error::ReadPem { .. })
+        ));
+    }
+
+    #[test]
+    fn cannot_decode_unknown_change_action() {
+        assert!(decode_change_action("action", ChangeAction::Delete as i32).is_ok());
+        assert!(decode_change_action("action", 42).is_err());
     }
 }
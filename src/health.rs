@@ -0,0 +1,178 @@
+//! The standard grpc health checking service, `grpc.health.v1.Health`.
+//!
+//! The server reports SERVING for the packybara service, as well as for the
+//! server as a whole (the empty service name), so long as a connection can be
+//! checked out of the pool and answer a trivial query. Otherwise it reports
+//! NOT_SERVING. Load balancers and supervisors may probe the server with
+//! `Check`, or subscribe to its status with `Watch`.
+//!
+//! The health service is registered alongside the packybara service, but is
+//! not subject to authentication, as the load balancer has no token to offer.
+use crate::pool::ConnectionPool;
+use crate::tasks::Tasks;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tonic::{Code, Request, Response, Status};
+
+pub mod pb {
+    tonic::include_proto!("grpc.health.v1");
+}
+pub use pb::health_check_response::ServingStatus;
+pub use pb::health_client::HealthClient;
+pub use pb::health_server::{Health, HealthServer};
+pub use pb::{HealthCheckRequest, HealthCheckResponse};
+
+/// The name under which the packybara service reports its health
+pub const SERVICE_NAME: &str = "packybara.Packybara";
+/// The interval at which Watch re-checks the status it streams
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Handle used to take the server out of service (eg while it drains),
+/// regardless of the state of the database.
+#[derive(Debug, Clone)]
+pub struct HealthReporter {
+    serving: Arc<AtomicBool>,
+}
+
+impl HealthReporter {
+    /// Report the server's status based on the state of the database
+    pub fn set_serving(&self) {
+        self.serving.store(true, Ordering::SeqCst);
+    }
+
+    /// Report NOT_SERVING, regardless of the state of the database
+    pub fn set_not_serving(&self) {
+        self.serving.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Implements `grpc.health.v1.Health`, checking the database on each request
+#[derive(Debug)]
+pub struct HealthService {
+    pool: ConnectionPool,
+    serving: Arc<AtomicBool>,
+    tasks: Tasks,
+}
+
+impl HealthService {
+    /// New up a HealthService, which checks the supplied pool. The tasks
+    /// streaming `Watch` responses are spawned on the supplied Tasks.
+    pub fn new(pool: ConnectionPool, tasks: Tasks) -> Self {
+        Self {
+            pool,
+            serving: Arc::new(AtomicBool::new(true)),
+            tasks,
+        }
+    }
+
+    /// Retrieve a HealthReporter for the service
+    pub fn reporter(&self) -> HealthReporter {
+        HealthReporter {
+            serving: self.serving.clone(),
+        }
+    }
+
+    /// The current status of the server
+    pub async fn status(&self) -> ServingStatus {
+        status(&self.pool, &self.serving).await
+    }
+}
+
+// Check the database, unless the server has been taken out of service
+async fn status(pool: &ConnectionPool, serving: &AtomicBool) -> ServingStatus {
+    if !serving.load(Ordering::SeqCst) {
+        return ServingStatus::NotServing;
+    }
+    let client = match pool.get().await {
+        Ok(client) => client,
+        Err(e) => {
+            log::warn!("health check unable to check out a connection: {}", e);
+            return ServingStatus::NotServing;
+        }
+    };
+    match client.simple_query("SELECT 1").await {
+        Ok(_) => ServingStatus::Serving,
+        Err(e) => {
+            log::warn!("health check query failed: {}", e);
+            ServingStatus::NotServing
+        }
+    }
+}
+
+/// Whether the server reports the health of the named service. The empty
+/// name refers to the server as a whole.
+pub fn is_known_service(service: &str) -> bool {
+    service.is_empty() || service == SERVICE_NAME
+}
+
+fn to_response(status: ServingStatus) -> HealthCheckResponse {
+    HealthCheckResponse {
+        status: status as i32,
+    }
+}
+
+#[tonic::async_trait]
+impl Health for HealthService {
+    async fn check(
+        &self,
+        request: Request<HealthCheckRequest>,
+    ) -> Result<Response<HealthCheckResponse>, Status> {
+        let HealthCheckRequest { service } = request.into_inner();
+        if !is_known_service(&service) {
+            return Err(Status::new(
+                Code::NotFound,
+                format!("unknown service '{}'", service),
+            ));
+        }
+        Ok(Response::new(to_response(self.status().await)))
+    }
+
+    type WatchStream = mpsc::Receiver<Result<HealthCheckResponse, Status>>;
+
+    // The status is re-sent at each interval, rather than only when it
+    // changes, so that the task notices when the client hangs up. The stream
+    // ends once the server has been taken out of service, so that it does
+    // not hold up a graceful shutdown.
+    async fn watch(
+        &self,
+        request: Request<HealthCheckRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        let HealthCheckRequest { service } = request.into_inner();
+        let known = is_known_service(&service);
+        let pool = self.pool.clone();
+        let serving = self.serving.clone();
+        let (mut tx, rx) = mpsc::channel(1);
+        self.tasks.spawn(async move {
+            loop {
+                let current = if known {
+                    status(&pool, &serving).await
+                } else {
+                    ServingStatus::ServiceUnknown
+                };
+                if tx.send(Ok(to_response(current))).await.is_err() {
+                    // the client has hung up
+                    break;
+                }
+                if !serving.load(Ordering::SeqCst) {
+                    break;
+                }
+                tokio::time::delay_for(WATCH_INTERVAL).await;
+            }
+        })?;
+        Ok(Response::new(rx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn knows_packybara_service() {
+        assert!(is_known_service(""));
+        assert!(is_known_service(SERVICE_NAME));
+        assert!(!is_known_service("packybara.Other"));
+    }
+}
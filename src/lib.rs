@@ -1,16 +1,43 @@
+pub use pb::changeset_op;
 pub use pb::packybara_client::PackybaraClient;
 pub use pb::packybara_server::{Packybara, PackybaraServer};
 pub use pb::{
-    Coords, VersionPinQueryReply, VersionPinQueryRequest, VersionPinsQueryReply,
-    VersionPinsQueryRequest, VersionPinsQueryRow,
+    AddReply, ChangeAction, ChangesQueryReply, ChangesQueryRequest, ChangesQueryRow, ChangesetOp,
+    ChangesetOpResult, ChangesetReply, ChangesetRequest, Coords, DistributionsDeleteReply,
+    DistributionsDeleteRequest, DistributionsQueryReply, DistributionsQueryRequest,
+    DistributionsQueryRow, IdsOp, LevelsAddRequest, LevelsQueryReply, LevelsQueryRequest,
+    LtreeSearchMode, NamesOp, OrderDirection, PackagesAddRequest, PackagesQueryReply,
+    PackagesQueryRequest, PackagesXmlChunk, PackagesXmlRequest, PinsQueryReply, PinsQueryRequest,
+    PinsQueryRow, PkgCoordsDeleteReply, PkgCoordsDeleteRequest, PkgCoordsQueryReply,
+    PkgCoordsQueryRequest, PkgCoordsQueryRow, PlatformsAddRequest, PlatformsQueryReply,
+    PlatformsQueryRequest, RevisionsQueryReply, RevisionsQueryRequest, RevisionsQueryRow,
+    RolesAddRequest, RolesQueryReply, RolesQueryRequest, RolesQueryRow, SearchAttribute,
+    SitesQueryReply, SitesQueryRequest, VersionPinChange, VersionPinQueryReply,
+    VersionPinQueryRequest, VersionPinWithsQueryReply, VersionPinWithsQueryRequest,
+    VersionPinWithsQueryRow, VersionPinsAddOp, VersionPinsAddRequest, VersionPinsDeleteReply,
+    VersionPinsDeleteRequest, VersionPinsQueryReply, VersionPinsQueryRequest, VersionPinsQueryRow,
+    VersionPinsSetRequest, VersionPinsWriteReply, WithsAddRequest, WithsDeleteReply,
+    WithsDeleteRequest, WithsQueryReply, WithsQueryRequest, WithsQueryRow, WithsRemoveRequest,
+    WithsReorderRequest, WithsWriteReply,
 };
 
 pub mod pb {
     tonic::include_proto!("packybara");
 }
 
+pub mod auth;
 pub mod service;
 pub use service::PackybaraService;
 pub mod client;
+pub mod config;
+pub mod health;
+pub use config::ServerConfig;
+pub mod packages_xml;
+pub mod page_token;
+pub mod pool;
+pub mod reflection;
+pub mod search;
+pub mod status;
+pub mod tasks;
 pub mod url;
 pub mod url_builder;
@@ -0,0 +1,109 @@
+//! Streaming of the packages.xml documents consumed by legacy launchers.
+//!
+//! The document is rendered by packybara's own packages.xml writer, so that
+//! it matches the files the launchers already read. The writer renders into a
+//! `ChunkWriter`, which hands the document on in chunks as it is written, so
+//! that it may be streamed to the client rather than built up in memory.
+use std::io::{self, Write};
+
+/// The size of the chunks in which a document is streamed
+pub const CHUNK_SIZE: usize = 16 * 1024;
+
+/// Buffers a document as it is written, handing it on in chunks of
+/// `CHUNK_SIZE` bytes, followed by whatever remains once it is flushed.
+///
+/// The chunks are handed to a function returning whether their receiver is
+/// still listening. Once it is not, writes fail with a BrokenPipe error, so
+/// that the writer stops rendering a document nobody will read.
+pub struct ChunkWriter<F> {
+    buffer: Vec<u8>,
+    send: F,
+}
+
+impl<F> ChunkWriter<F>
+where
+    F: FnMut(Vec<u8>) -> bool,
+{
+    /// New up a ChunkWriter handing its chunks to the supplied function
+    pub fn new(send: F) -> Self {
+        Self {
+            buffer: Vec::with_capacity(CHUNK_SIZE),
+            send,
+        }
+    }
+
+    fn send_chunk(&mut self, len: usize) -> io::Result<()> {
+        let rest = self.buffer.split_off(len);
+        let chunk = std::mem::replace(&mut self.buffer, rest);
+        if (self.send)(chunk) {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "the receiver of the document hung up",
+            ))
+        }
+    }
+}
+
+impl<F> Write for ChunkWriter<F>
+where
+    F: FnMut(Vec<u8>) -> bool,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        while self.buffer.len() >= CHUNK_SIZE {
+            self.send_chunk(CHUNK_SIZE)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        self.send_chunk(self.buffer.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_write_in_chunks() {
+        let mut chunks = Vec::new();
+        let mut writer = ChunkWriter::new(|chunk| {
+            chunks.push(chunk);
+            true
+        });
+        writer.write_all(&vec![b'a'; CHUNK_SIZE + 10]).unwrap();
+        writer.write_all(b"bc").unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].len(), CHUNK_SIZE);
+        assert_eq!(chunks[1].len(), 12);
+        assert!(chunks[1].ends_with(b"abc"));
+    }
+
+    #[test]
+    fn flushing_nothing_sends_nothing() {
+        let mut sent = 0;
+        let mut writer = ChunkWriter::new(|_| {
+            sent += 1;
+            true
+        });
+        writer.flush().unwrap();
+        drop(writer);
+        assert_eq!(sent, 0);
+    }
+
+    #[test]
+    fn cannot_write_once_receiver_hangs_up() {
+        let mut writer = ChunkWriter::new(|_| false);
+        writer.write_all(b"<packages/>").unwrap();
+        let err = writer.flush().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+}
@@ -0,0 +1,222 @@
+//! Opaque tokens used to page through the results of list rpcs.
+//!
+//! A token records a keyset cursor: the values of the ordering columns of the
+//! last row of a page, along with that row's id, which breaks ties between
+//! rows sharing those values. The next page is selected by the query itself,
+//! as the rows ordered after the cursor, so that pages neither skip nor repeat
+//! rows when rows are added or removed between requests.
+//!
+//! A token also records a fingerprint of the query which produced it. A token
+//! presented alongside a different query is rejected, rather than silently
+//! returning an unrelated page.
+use crate::status;
+use packybara::types::IdType;
+use std::hash::{Hash, Hasher};
+use tonic::Status;
+
+/// The position of the next page of a query's results
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PageToken {
+    values: Vec<String>,
+    id: IdType,
+    returned: u64,
+    fingerprint: u64,
+}
+
+impl PageToken {
+    /// New up a PageToken
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The values of the ordering columns of the last row returned
+    /// * `id` - The id of the last row returned
+    /// * `returned` - The number of rows returned by this and prior pages
+    /// * `fingerprint` - The fingerprint of the query being paged through
+    ///
+    /// # Returns
+    ///
+    /// * PageToken
+    pub fn new(values: Vec<String>, id: IdType, returned: u64, fingerprint: u64) -> Self {
+        Self {
+            values,
+            id,
+            returned,
+            fingerprint,
+        }
+    }
+
+    /// The values of the ordering columns of the last row returned
+    pub fn values(&self) -> &[String] {
+        &self.values
+    }
+
+    /// The id of the last row returned
+    pub fn id(&self) -> IdType {
+        self.id
+    }
+
+    /// The number of rows returned by prior pages
+    pub fn returned(&self) -> u64 {
+        self.returned
+    }
+
+    /// Encode the token as an opaque string
+    pub fn encode(&self) -> String {
+        let mut token = format!(
+            "{:016x}{:016x}{:08x}",
+            self.fingerprint, self.returned, self.id as u32
+        );
+        for value in &self.values {
+            token.push('.');
+            for byte in value.as_bytes() {
+                token.push_str(&format!("{:02x}", byte));
+            }
+        }
+        token
+    }
+
+    /// Decode a token previously produced by `encode`. A malformed token
+    /// results in an InvalidArgument Status.
+    pub fn decode(token: &str) -> Result<Self, Status> {
+        let invalid = || status::invalid_argument(format!("invalid page_token '{}'", token));
+        if !token.is_ascii() {
+            return Err(invalid());
+        }
+        let mut parts = token.split('.');
+        let head = parts.next().unwrap_or("");
+        if head.len() != 40 {
+            return Err(invalid());
+        }
+        let fingerprint = u64::from_str_radix(&head[..16], 16).map_err(|_| invalid())?;
+        let returned = u64::from_str_radix(&head[16..32], 16).map_err(|_| invalid())?;
+        let id = u32::from_str_radix(&head[32..], 16).map_err(|_| invalid())? as IdType;
+        let values = parts
+            .map(|part| {
+                if part.len() % 2 != 0 {
+                    return Err(invalid());
+                }
+                let bytes = (0..part.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&part[i..i + 2], 16).map_err(|_| invalid()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                String::from_utf8(bytes).map_err(|_| invalid())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            values,
+            id,
+            returned,
+            fingerprint,
+        })
+    }
+
+    /// Retrieve the cursor from which to resume the page requested by an
+    /// optional token. The first page has none. A token produced by a
+    /// different query results in an InvalidArgument Status.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The page_token supplied with the request, if any
+    /// * `fingerprint` - The fingerprint of the request's query
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - Option wrapped PageToken
+    /// - Err - Status
+    pub fn resume(token: Option<&str>, fingerprint: u64) -> Result<Option<Self>, Status> {
+        match token {
+            None | Some("") => Ok(None),
+            Some(token) => {
+                let token = Self::decode(token)?;
+                if token.fingerprint != fingerprint {
+                    return Err(status::invalid_argument(
+                        "page_token was issued for a different query",
+                    ));
+                }
+                Ok(Some(token))
+            }
+        }
+    }
+}
+
+/// The 64 bit FNV-1a hash. Unlike std's DefaultHasher, its output is fixed,
+/// so tokens remain valid across builds of the server.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Compute the fingerprint of a query from its parameters. Paging parameters
+/// should be excluded, as they vary from page to page.
+pub fn fingerprint<H: Hash>(query: &H) -> u64 {
+    let mut hasher = Fnv1a::default();
+    query.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+
+    #[test]
+    fn can_round_trip_token() {
+        let token = PageToken::new(
+            vec![
+                "maya".to_string(),
+                "dev01.rd.9999".to_string(),
+                String::new(),
+            ],
+            100,
+            20,
+            0xdead_beef,
+        );
+        assert_eq!(PageToken::decode(&token.encode()).unwrap(), token);
+        let token = PageToken::new(Vec::new(), 7, 0, 1);
+        assert_eq!(PageToken::decode(&token.encode()).unwrap(), token);
+    }
+
+    #[test]
+    fn cannot_decode_malformed_token() {
+        let err = PageToken::decode("not a token").unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+        let token = PageToken::new(vec!["maya".to_string()], 1, 0, 1).encode();
+        let err = PageToken::decode(&format!("{}f", token)).unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+
+    #[test]
+    fn cannot_resume_from_token_of_other_query() {
+        let token = PageToken::new(vec!["maya".to_string()], 10, 2, fingerprint(&"maya"));
+        let encoded = token.encode();
+        assert_eq!(
+            PageToken::resume(Some(&encoded), fingerprint(&"maya")).unwrap(),
+            Some(token)
+        );
+        assert_eq!(PageToken::resume(None, fingerprint(&"maya")).unwrap(), None);
+        let err = PageToken::resume(Some(&encoded), fingerprint(&"houdini")).unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+
+    #[test]
+    fn fingerprint_is_stable() {
+        assert_eq!(fingerprint(&"maya"), 0x5325_41a4_be85_300e);
+    }
+}
@@ -0,0 +1,60 @@
+//! Pooled database connections for the packybara grpc server.
+//!
+//! Connections are checked out of the pool for the duration of a single
+//! request. Connections which have been closed by the server (eg when
+//! postgres is restarted) are discarded and replaced on demand.
+use crate::config::ServerConfig;
+use bb8::{Pool, PooledConnection, RunError};
+use bb8_postgres::PostgresConnectionManager;
+use tokio_postgres::NoTls;
+
+/// The connection manager used by the pool
+pub type ConnectionManager = PostgresConnectionManager<NoTls>;
+/// Pool of database connections
+pub type ConnectionPool = Pool<ConnectionManager>;
+/// A connection checked out of the ConnectionPool
+pub type PooledClient<'a> = PooledConnection<'a, ConnectionManager>;
+/// Error returned when a connection cannot be checked out of the pool
+pub type PoolError = RunError<tokio_postgres::Error>;
+
+/// Build a ConnectionPool from the db and pool settings of the supplied
+/// ServerConfig, establishing the minimum number of idle connections.
+///
+/// # Arguments
+///
+/// * `config` - Reference to the ServerConfig
+///
+/// # Returns
+///
+/// * Result
+/// - Ok - ConnectionPool
+/// - Err - tokio_postgres::Error
+pub async fn build_pool(config: &ServerConfig) -> Result<ConnectionPool, tokio_postgres::Error> {
+    let manager = PostgresConnectionManager::new(config.db.pg_config(), NoTls);
+    Pool::builder()
+        .max_size(config.pool.max_size)
+        .min_idle(config.pool.min_idle)
+        .connection_timeout(config.pool.connection_timeout())
+        .test_on_check_out(config.pool.test_on_check_out)
+        .build(manager)
+        .await
+}
+
+/// Close the pool's connections, by dropping the supplied handle to the pool.
+/// The connections are closed once the last handle has been dropped, so the
+/// services, and any tasks they spawned (see `tasks::Tasks::stop`), must be
+/// dropped first. A request abandoned at the end of the shutdown grace period
+/// may still hold a connection, which is then closed with the runtime.
+///
+/// # Arguments
+///
+/// * `pool` - The server's handle to the ConnectionPool
+pub fn close(pool: ConnectionPool) {
+    let state = pool.state();
+    log::info!(
+        "closing database connections ({} open, {} idle)",
+        state.connections,
+        state.idle_connections
+    );
+    drop(pool);
+}
@@ -0,0 +1,357 @@
+//! The grpc server reflection service, `grpc.reflection.v1alpha.ServerReflection`.
+//!
+//! Reflection allows generic grpc tools (eg grpcurl) to list the server's
+//! services and retrieve the descriptors of their messages, without being
+//! handed `packybara.proto`. The descriptors are taken from the file
+//! descriptor set emitted by `build.rs`. The service is always registered,
+//! but answers Unimplemented, as though it were absent, unless the server
+//! config enables it.
+use crate::tasks::Tasks;
+use prost::Message;
+use prost_types::{DescriptorProto, FileDescriptorProto, FileDescriptorSet};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tonic::{Code, Request, Response, Status, Streaming};
+
+pub mod pb {
+    tonic::include_proto!("grpc.reflection.v1alpha");
+}
+pub use pb::server_reflection_server::{ServerReflection, ServerReflectionServer};
+use pb::{
+    server_reflection_request::MessageRequest, server_reflection_response::MessageResponse,
+    ErrorResponse, ExtensionNumberResponse, FileDescriptorResponse, ListServiceResponse,
+    ServerReflectionRequest, ServerReflectionResponse, ServiceResponse,
+};
+
+/// The file descriptor set of the protos served, emitted by `build.rs`
+pub const FILE_DESCRIPTOR_SET: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/packybara_descriptor.bin"));
+
+/// Index of the files, and the symbols they declare, in a file descriptor set
+#[derive(Debug, Default)]
+pub struct Descriptors {
+    // file name -> file
+    files: HashMap<String, FileDescriptorProto>,
+    // fully qualified symbol -> file name
+    symbols: HashMap<String, String>,
+    // fully qualified service names
+    services: Vec<String>,
+}
+
+impl Descriptors {
+    /// Index an encoded FileDescriptorSet
+    ///
+    /// # Arguments
+    ///
+    /// * `encoded` - The FileDescriptorSet, as emitted by protoc
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - Descriptors
+    /// - Err - prost::DecodeError
+    pub fn decode(encoded: &[u8]) -> Result<Self, prost::DecodeError> {
+        let set = FileDescriptorSet::decode(encoded)?;
+        let mut descriptors = Self::default();
+        for file in set.file {
+            descriptors.add_file(file);
+        }
+        descriptors.services.sort();
+        Ok(descriptors)
+    }
+
+    fn add_file(&mut self, file: FileDescriptorProto) {
+        let name = file.name().to_string();
+        let package = file.package();
+        for service in &file.service {
+            let service_name = qualify(package, service.name());
+            for method in &service.method {
+                self.symbols
+                    .insert(qualify(&service_name, method.name()), name.clone());
+            }
+            self.symbols.insert(service_name.clone(), name.clone());
+            self.services.push(service_name);
+        }
+        for message in &file.message_type {
+            self.add_message(package, message, &name);
+        }
+        for enumeration in &file.enum_type {
+            self.symbols
+                .insert(qualify(package, enumeration.name()), name.clone());
+        }
+        self.files.insert(name, file);
+    }
+
+    fn add_message(&mut self, scope: &str, message: &DescriptorProto, file: &str) {
+        let message_name = qualify(scope, message.name());
+        for nested in &message.nested_type {
+            self.add_message(&message_name, nested, file);
+        }
+        for enumeration in &message.enum_type {
+            self.symbols
+                .insert(qualify(&message_name, enumeration.name()), file.to_string());
+        }
+        self.symbols.insert(message_name, file.to_string());
+    }
+
+    /// The fully qualified names of the services described
+    pub fn services(&self) -> &[String] {
+        &self.services
+    }
+
+    /// Retrieve the encoded file, followed by its transitive dependencies
+    pub fn file_by_filename(&self, name: &str) -> Option<Vec<Vec<u8>>> {
+        if !self.files.contains_key(name) {
+            return None;
+        }
+        let mut seen = HashSet::new();
+        let mut encoded = Vec::new();
+        self.encode_with_dependencies(name, &mut seen, &mut encoded);
+        Some(encoded)
+    }
+
+    /// Retrieve the encoded file declaring the fully qualified symbol,
+    /// followed by its transitive dependencies
+    pub fn file_containing_symbol(&self, symbol: &str) -> Option<Vec<Vec<u8>>> {
+        let symbol = symbol.trim_start_matches('.');
+        self.symbols
+            .get(symbol)
+            .and_then(|name| self.file_by_filename(name))
+    }
+
+    fn encode_with_dependencies(
+        &self,
+        name: &str,
+        seen: &mut HashSet<String>,
+        encoded: &mut Vec<Vec<u8>>,
+    ) {
+        if !seen.insert(name.to_string()) {
+            return;
+        }
+        let file = match self.files.get(name) {
+            Some(file) => file,
+            None => return,
+        };
+        let mut bytes = Vec::new();
+        // encoding into a Vec cannot run out of space
+        file.encode(&mut bytes)
+            .expect("unable to encode file descriptor");
+        encoded.push(bytes);
+        for dependency in &file.dependency {
+            self.encode_with_dependencies(dependency, seen, encoded);
+        }
+    }
+
+    /// Answer a single reflection request
+    pub fn respond(&self, request: ServerReflectionRequest) -> ServerReflectionResponse {
+        let not_found = |message: String| {
+            MessageResponse::ErrorResponse(ErrorResponse {
+                error_code: Code::NotFound as i32,
+                error_message: message,
+            })
+        };
+        let files = |files: Vec<Vec<u8>>| {
+            MessageResponse::FileDescriptorResponse(FileDescriptorResponse {
+                file_descriptor_proto: files,
+            })
+        };
+        let response = match &request.message_request {
+            Some(MessageRequest::ListServices(_)) => {
+                MessageResponse::ListServicesResponse(ListServiceResponse {
+                    service: self
+                        .services
+                        .iter()
+                        .map(|name| ServiceResponse { name: name.clone() })
+                        .collect(),
+                })
+            }
+            Some(MessageRequest::FileByFilename(name)) => match self.file_by_filename(name) {
+                Some(encoded) => files(encoded),
+                None => not_found(format!("unknown file '{}'", name)),
+            },
+            Some(MessageRequest::FileContainingSymbol(symbol)) => {
+                match self.file_containing_symbol(symbol) {
+                    Some(encoded) => files(encoded),
+                    None => not_found(format!("unknown symbol '{}'", symbol)),
+                }
+            }
+            // the protos declare no extensions
+            Some(MessageRequest::FileContainingExtension(extension)) => not_found(format!(
+                "unknown extension {} of '{}'",
+                extension.extension_number, extension.containing_type
+            )),
+            Some(MessageRequest::AllExtensionNumbersOfType(base_type_name)) => {
+                MessageResponse::AllExtensionNumbersResponse(ExtensionNumberResponse {
+                    base_type_name: base_type_name.clone(),
+                    extension_number: Vec::new(),
+                })
+            }
+            None => MessageResponse::ErrorResponse(ErrorResponse {
+                error_code: Code::InvalidArgument as i32,
+                error_message: "empty request".to_string(),
+            }),
+        };
+        ServerReflectionResponse {
+            valid_host: request.host.clone(),
+            original_request: Some(request),
+            message_response: Some(response),
+        }
+    }
+}
+
+// Qualify a name by the package or message in which it is declared
+fn qualify(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+/// Implements `grpc.reflection.v1alpha.ServerReflection`
+#[derive(Debug, Clone)]
+pub struct ReflectionService {
+    // None when reflection is disabled
+    descriptors: Option<Arc<Descriptors>>,
+    tasks: Tasks,
+}
+
+impl ReflectionService {
+    /// New up a ReflectionService describing the protos built with the crate.
+    /// The tasks answering its streams are spawned on the supplied Tasks.
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks` - The Tasks on which to spawn the tasks answering streams
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - ReflectionService
+    /// - Err - prost::DecodeError
+    pub fn new(tasks: Tasks) -> Result<Self, prost::DecodeError> {
+        Self::from_file_descriptor_set(FILE_DESCRIPTOR_SET, tasks)
+    }
+
+    /// New up a ReflectionService describing an encoded FileDescriptorSet
+    pub fn from_file_descriptor_set(
+        encoded: &[u8],
+        tasks: Tasks,
+    ) -> Result<Self, prost::DecodeError> {
+        Ok(Self {
+            descriptors: Some(Arc::new(Descriptors::decode(encoded)?)),
+            tasks,
+        })
+    }
+
+    /// New up a ReflectionService which refuses every request, as reflection
+    /// is disabled
+    pub fn disabled(tasks: Tasks) -> Self {
+        Self {
+            descriptors: None,
+            tasks,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl ServerReflection for ReflectionService {
+    type ServerReflectionInfoStream = mpsc::Receiver<Result<ServerReflectionResponse, Status>>;
+
+    async fn server_reflection_info(
+        &self,
+        request: Request<Streaming<ServerReflectionRequest>>,
+    ) -> Result<Response<Self::ServerReflectionInfoStream>, Status> {
+        let descriptors = match self.descriptors {
+            Some(ref descriptors) => descriptors.clone(),
+            None => return Err(Status::new(Code::Unimplemented, "reflection is disabled")),
+        };
+        let mut requests = request.into_inner();
+        let (mut tx, rx) = mpsc::channel(1);
+        self.tasks.spawn(async move {
+            loop {
+                let response = match requests.message().await {
+                    Ok(Some(request)) => Ok(descriptors.respond(request)),
+                    Ok(None) => break,
+                    Err(status) => Err(status),
+                };
+                let failed = response.is_err();
+                // the client may already have hung up
+                if tx.send(response).await.is_err() || failed {
+                    break;
+                }
+            }
+        })?;
+        Ok(Response::new(rx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(message_request: MessageRequest) -> ServerReflectionRequest {
+        ServerReflectionRequest {
+            host: String::new(),
+            message_request: Some(message_request),
+        }
+    }
+
+    fn file_names(response: ServerReflectionResponse) -> Vec<String> {
+        match response.message_response {
+            Some(MessageResponse::FileDescriptorResponse(FileDescriptorResponse {
+                file_descriptor_proto,
+            })) => file_descriptor_proto
+                .iter()
+                .map(|bytes| {
+                    FileDescriptorProto::decode(bytes.as_slice())
+                        .unwrap()
+                        .name()
+                        .to_string()
+                })
+                .collect(),
+            other => panic!("unexpected response {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lists_services() {
+        let descriptors = Descriptors::decode(FILE_DESCRIPTOR_SET).unwrap();
+        assert!(descriptors
+            .services()
+            .contains(&"packybara.Packybara".to_string()));
+        assert!(descriptors
+            .services()
+            .contains(&"grpc.health.v1.Health".to_string()));
+    }
+
+    #[test]
+    fn can_find_file_containing_symbol() {
+        let descriptors = Descriptors::decode(FILE_DESCRIPTOR_SET).unwrap();
+        let response = descriptors.respond(request(MessageRequest::FileContainingSymbol(
+            "packybara.Packybara.GetVersionPins".to_string(),
+        )));
+        assert_eq!(
+            file_names(response),
+            vec![
+                "packybara.proto".to_string(),
+                "google/protobuf/timestamp.proto".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unknown_symbol() {
+        let descriptors = Descriptors::decode(FILE_DESCRIPTOR_SET).unwrap();
+        let response = descriptors.respond(request(MessageRequest::FileContainingSymbol(
+            "packybara.Nope".to_string(),
+        )));
+        match response.message_response {
+            Some(MessageResponse::ErrorResponse(ErrorResponse { error_code, .. })) => {
+                assert_eq!(error_code, Code::NotFound as i32)
+            }
+            other => panic!("unexpected response {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! Conversions between the protobuf search enums and their packybara counterparts.
+//!
+//! The protobuf enums travel on the wire as i32s, so the service decodes them
+//! with `from_i32`, rejecting values it does not recognize. They may also be
+//! parsed from the same strings packybara accepts (eg `ancestor` or `down`),
+//! which lets the command line client take them as arguments.
+use crate::{LtreeSearchMode, OrderDirection, SearchAttribute};
+use packybara::{
+    LtreeSearchMode as PLtreeSearchMode, OrderDirection as POrderDirection,
+    SearchAttribute as PSearchAttribute,
+};
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+impl From<LtreeSearchMode> for PLtreeSearchMode {
+    fn from(mode: LtreeSearchMode) -> Self {
+        match mode {
+            LtreeSearchMode::Ancestor => PLtreeSearchMode::Ancestor,
+            LtreeSearchMode::Exact => PLtreeSearchMode::Exact,
+            LtreeSearchMode::Descendant => PLtreeSearchMode::Descendant,
+        }
+    }
+}
+
+impl From<PLtreeSearchMode> for LtreeSearchMode {
+    fn from(mode: PLtreeSearchMode) -> Self {
+        match mode {
+            PLtreeSearchMode::Ancestor => LtreeSearchMode::Ancestor,
+            PLtreeSearchMode::Exact => LtreeSearchMode::Exact,
+            PLtreeSearchMode::Descendant => LtreeSearchMode::Descendant,
+        }
+    }
+}
+
+impl FromStr for LtreeSearchMode {
+    type Err = String;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        PLtreeSearchMode::from_str(mode)
+            .map(LtreeSearchMode::from)
+            .map_err(|e| format!("invalid search mode '{}': {}", mode, e))
+    }
+}
+
+impl From<SearchAttribute> for PSearchAttribute {
+    fn from(attribute: SearchAttribute) -> Self {
+        match attribute {
+            SearchAttribute::Package => PSearchAttribute::Package,
+            SearchAttribute::Level => PSearchAttribute::Level,
+            SearchAttribute::Role => PSearchAttribute::Role,
+            SearchAttribute::Platform => PSearchAttribute::Platform,
+            SearchAttribute::Site => PSearchAttribute::Site,
+        }
+    }
+}
+
+/// packybara's SearchAttribute carries an Unknown variant, which has no
+/// protobuf counterpart.
+impl TryFrom<PSearchAttribute> for SearchAttribute {
+    type Error = String;
+
+    fn try_from(attribute: PSearchAttribute) -> Result<Self, Self::Error> {
+        match attribute {
+            PSearchAttribute::Package => Ok(SearchAttribute::Package),
+            PSearchAttribute::Level => Ok(SearchAttribute::Level),
+            PSearchAttribute::Role => Ok(SearchAttribute::Role),
+            PSearchAttribute::Platform => Ok(SearchAttribute::Platform),
+            PSearchAttribute::Site => Ok(SearchAttribute::Site),
+            PSearchAttribute::Unknown => Err("unknown search attribute".to_string()),
+        }
+    }
+}
+
+impl FromStr for SearchAttribute {
+    type Err = String;
+
+    fn from_str(attribute: &str) -> Result<Self, Self::Err> {
+        PSearchAttribute::from_str(attribute.trim())
+            .map_err(|e| e.to_string())
+            .and_then(SearchAttribute::try_from)
+            .map_err(|e| format!("invalid search attribute '{}': {}", attribute, e))
+    }
+}
+
+impl From<OrderDirection> for POrderDirection {
+    fn from(direction: OrderDirection) -> Self {
+        match direction {
+            OrderDirection::Asc => POrderDirection::Asc,
+            OrderDirection::Desc => POrderDirection::Desc,
+        }
+    }
+}
+
+impl From<POrderDirection> for OrderDirection {
+    fn from(direction: POrderDirection) -> Self {
+        match direction {
+            POrderDirection::Asc => OrderDirection::Asc,
+            POrderDirection::Desc => OrderDirection::Desc,
+        }
+    }
+}
+
+impl FromStr for OrderDirection {
+    type Err = String;
+
+    fn from_str(direction: &str) -> Result<Self, Self::Err> {
+        POrderDirection::from_str(direction)
+            .map(OrderDirection::from)
+            .map_err(|e| format!("invalid order direction '{}': {}", direction, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_search_mode() {
+        assert_eq!(
+            LtreeSearchMode::from_str("ancestor"),
+            Ok(LtreeSearchMode::Ancestor)
+        );
+        assert_eq!(
+            LtreeSearchMode::from_str("exact"),
+            Ok(LtreeSearchMode::Exact)
+        );
+        assert!(LtreeSearchMode::from_str("sideways").is_err());
+    }
+
+    #[test]
+    fn can_parse_search_attribute() {
+        assert_eq!(
+            SearchAttribute::from_str("level"),
+            Ok(SearchAttribute::Level)
+        );
+        assert_eq!(
+            SearchAttribute::from_str(" site"),
+            Ok(SearchAttribute::Site)
+        );
+        assert!(SearchAttribute::from_str("colour").is_err());
+    }
+
+    #[test]
+    fn can_parse_order_direction() {
+        assert_eq!(OrderDirection::from_str("asc"), Ok(OrderDirection::Asc));
+        assert_eq!(OrderDirection::from_str("desc"), Ok(OrderDirection::Desc));
+        assert!(OrderDirection::from_str("up").is_err());
+    }
+
+    #[test]
+    fn can_round_trip_through_packybara() {
+        for attribute in vec![
+            SearchAttribute::Package,
+            SearchAttribute::Level,
+            SearchAttribute::Role,
+            SearchAttribute::Platform,
+            SearchAttribute::Site,
+        ] {
+            let attr = PSearchAttribute::from(attribute);
+            assert_eq!(SearchAttribute::try_from(attr), Ok(attribute));
+        }
+        assert!(SearchAttribute::try_from(PSearchAttribute::Unknown).is_err());
+        assert_eq!(
+            OrderDirection::from(POrderDirection::from(OrderDirection::Desc)),
+            OrderDirection::Desc
+        );
+        assert_eq!(
+            LtreeSearchMode::from(PLtreeSearchMode::from(LtreeSearchMode::Descendant)),
+            LtreeSearchMode::Descendant
+        );
+    }
+}
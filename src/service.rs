@@ -1,66 +1,306 @@
+use chrono::{DateTime, Local};
 use log;
 use packybara::coords::Coords as PCoords;
+use packybara::db::changeset::Changeset;
+use packybara::db::find::pins::FindPinsRow;
+use packybara::db::find::versionpin_withs::FindVersionPinWithsRow;
 use packybara::db::find::versionpins::FindVersionPinsRow;
-use packybara::db::find_all::versionpins::FindAllVersionPinsRow;
+use packybara::db::find::withs::FindWithsRow;
+use packybara::db::find_all::changes::{ChangeAction as PChangeAction, FindAllChangesRow};
+use packybara::db::find_all::distributions::FindAllDistributionsRow;
+use packybara::db::find_all::levels::FindAllLevelsRow;
+use packybara::db::find_all::packages::FindAllPackagesRow;
+use packybara::db::find_all::pkgcoords::FindAllPkgCoordsRow;
+use packybara::db::find_all::platforms::FindAllPlatformsRow;
+use packybara::db::find_all::revisions::FindAllRevisionsRow;
+use packybara::db::find_all::roles::FindAllRolesRow;
+use packybara::db::find_all::sites::FindAllSitesRow;
+use packybara::db::find_all::versionpins::{FindAllVersionPins, FindAllVersionPinsRow};
 use packybara::db::traits::*;
-use packybara::packrat::{Client, PackratDb};
-use packybara::LtreeSearchMode;
-use packybara::{OrderDirection, SearchAttribute};
+use packybara::io::packages_xml::xml::write_xml;
+use packybara::packrat::PackratDb;
+use packybara::types::{IdType, LongIdType};
+use packybara::LtreeSearchMode as PLtreeSearchMode;
+use packybara::{
+    OrderDirection as POrderDirection, OrderLevelBy, OrderPkgCoordsBy, OrderPlatformBy,
+    OrderRevisionBy, OrderRoleBy, SearchAttribute as PSearchAttribute,
+};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::future::Future;
+use std::io::Write;
 use std::str::FromStr;
-use tokio_postgres::NoTls;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tokio::stream::StreamExt;
+use tokio::sync::{mpsc, oneshot};
+use tokio_postgres::{Client, Transaction};
 use tonic::transport::Server;
-use tonic::{Code, Request, Response, Status};
+use tonic::{Code, Interceptor, Request, Response, Status};
 
 use crate::{
-    url::GrpcUrl, Coords, Packybara, PackybaraServer, VersionPinQueryReply, VersionPinQueryRequest,
-    VersionPinsQueryReply, VersionPinsQueryRequest, VersionPinsQueryRow,
+    auth::{self, AuthenticatorExt, Caller, Policy, Scope},
+    changeset_op::Op,
+    config::ServerConfig,
+    health::{HealthServer, HealthService},
+    packages_xml::ChunkWriter,
+    page_token::{self, PageToken},
+    pool::{self, ConnectionPool, PooledClient},
+    reflection::{ReflectionService, ServerReflectionServer},
+    status::{self, IntoStatus},
+    tasks::Tasks,
+    AddReply, ChangeAction, ChangesQueryReply, ChangesQueryRequest, ChangesQueryRow, ChangesetOp,
+    ChangesetOpResult, ChangesetReply, ChangesetRequest, Coords, DistributionsDeleteReply,
+    DistributionsDeleteRequest, DistributionsQueryReply, DistributionsQueryRequest,
+    DistributionsQueryRow, IdsOp, LevelsAddRequest, LevelsQueryReply, LevelsQueryRequest,
+    LtreeSearchMode, NamesOp, OrderDirection, PackagesAddRequest, PackagesQueryReply,
+    PackagesQueryRequest, PackagesXmlChunk, PackagesXmlRequest, Packybara, PackybaraServer,
+    PinsQueryReply, PinsQueryRequest, PinsQueryRow, PkgCoordsDeleteReply, PkgCoordsDeleteRequest,
+    PkgCoordsQueryReply, PkgCoordsQueryRequest, PkgCoordsQueryRow, PlatformsAddRequest,
+    PlatformsQueryReply, PlatformsQueryRequest, RevisionsQueryReply, RevisionsQueryRequest,
+    RevisionsQueryRow, RolesAddRequest, RolesQueryReply, RolesQueryRequest, RolesQueryRow,
+    SearchAttribute, SitesQueryReply, SitesQueryRequest, VersionPinChange, VersionPinQueryReply,
+    VersionPinQueryRequest, VersionPinWithsQueryReply, VersionPinWithsQueryRequest,
+    VersionPinWithsQueryRow, VersionPinsAddOp, VersionPinsAddRequest, VersionPinsDeleteReply,
+    VersionPinsDeleteRequest, VersionPinsQueryReply, VersionPinsQueryRequest, VersionPinsQueryRow,
+    VersionPinsSetRequest, VersionPinsWriteReply, WithsAddRequest, WithsDeleteOp, WithsDeleteReply,
+    WithsDeleteRequest, WithsOp, WithsQueryReply, WithsQueryRequest, WithsQueryRow,
+    WithsRemoveRequest, WithsReorderRequest, WithsWriteReply,
 };
+/// The number of rows a streaming rpc buffers ahead of the client
+const STREAM_BUFFER_SIZE: usize = 64;
+
 #[derive(Debug)]
 pub struct PackybaraService {
-    client: Client,
+    pool: ConnectionPool,
+    policy: Option<Arc<Policy>>,
+    tasks: Tasks,
 }
 
 impl PackybaraService {
-    pub fn new(client: Client) -> Self {
-        Self { client }
+    /// New up a PackybaraService, serving requests from the supplied pool.
+    /// The tasks feeding streaming responses are spawned on the supplied Tasks.
+    pub fn new(pool: ConnectionPool, tasks: Tasks) -> Self {
+        Self {
+            pool,
+            policy: None,
+            tasks,
+        }
+    }
+
+    /// Given a mutable instance of Self and an Option wrapped Policy, set
+    /// the policy governing writes if supplied and return Self, following
+    /// the common builder pattern. Without a policy, any caller may write.
+    pub fn policy_opt(mut self, policy: Option<Policy>) -> Self {
+        if let Some(policy) = policy {
+            self.policy = Some(Arc::new(policy));
+        }
+        self
     }
-    // TODO:: Add configuration as run argument
-    /// Run the server as a service.
+
+    /// Run the server as a service, connecting to the database and binding
+    /// to the url described by the supplied ServerConfig. The server serves
+    /// tls when the url's scheme is https, using the config's tls settings.
+    /// Requests are authenticated when the config names a tokens file, and
+    /// writes are authorized when it names a policy file. The grpc health
+    /// service is registered alongside the packybara service, as is the
+    /// reflection service when the config enables it.
+    ///
+    /// The server runs until the `shutdown` future resolves. It then reports
+    /// NOT_SERVING for the config's shutdown delay, while it continues to
+    /// serve requests, so that health probes may take it out of rotation.
+    /// It then stops accepting connections, and gives in-flight requests the
+    /// config's grace period to complete. Streams still running are then
+    /// stopped, before the database connections are closed.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The ServerConfig instance
+    /// * `shutdown` - Future which resolves when the server should shut down
     ///
     /// # Examples
-    /// ```
+    /// ```no_run
     /// use tokio;
-    /// use packybara_grpc::PackybaraService;
+    /// use packybara_grpc::{PackybaraService, ServerConfig};
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    ///     PackybaraService::run().await?;
+    ///     let config = ServerConfig::load(None)?;
+    ///     let shutdown = async {
+    ///         tokio::signal::ctrl_c().await.ok();
+    ///     };
+    ///     PackybaraService::run(config, shutdown).await?;
     ///     Ok(())
     /// }
     /// ```
-    pub async fn run(url: GrpcUrl) -> Result<(), Box<dyn std::error::Error>> {
-        let (client, connection) = tokio_postgres::connect(
-            "host=127.0.0.1 user=postgres  dbname=packrat password=example port=5432",
-            NoTls,
-        )
-        .await?;
-        tokio::spawn(async move {
-            if let Err(e) = connection.await {
-                eprintln!("connection error: {}", e);
-            }
-        });
-        let addr = url.to_socket_addr()?; //"[::1]:50051".parse()?;
-        let packy = PackybaraService::new(client);
-        Server::builder()
-            .add_service(PackybaraServer::new(packy))
-            .serve(addr)
-            .await?;
+    pub async fn run<F>(config: ServerConfig, shutdown: F) -> Result<(), Box<dyn std::error::Error>>
+    where
+        F: Future<Output = ()>,
+    {
+        let tls = config.server_tls_config()?;
+        let (tokens, policy) = config.auth.load()?;
+        let pool = pool::build_pool(&config).await?;
+        let addr = config.grpc_url()?.to_socket_addr()?;
+        let tasks = Tasks::new();
+        let health = HealthService::new(pool.clone(), tasks.clone());
+        let packy = PackybaraService::new(pool.clone(), tasks.clone()).policy_opt(policy);
+        let mut server = Server::builder();
+        if let Some(ref tls) = tls {
+            if config.tls.is_mutual() {
+                log::info!("requiring client certificates");
+            }
+            server = server.tls_config(tls);
+        }
+        let interceptor: Interceptor = match tokens {
+            Some(tokens) => {
+                log::info!("authenticating requests with bearer tokens");
+                tokens.into_interceptor()
+            }
+            None => auth::discard_user.into(),
+        };
+        let reporter = health.reporter();
+        let (drain_tx, draining) = oneshot::channel();
+        let delay = config.shutdown_delay();
+        let signal = async move {
+            shutdown.await;
+            log::info!(
+                "shutting down, reporting NOT_SERVING for {}s",
+                delay.as_secs()
+            );
+            reporter.set_not_serving();
+            tokio::time::delay_for(delay).await;
+            log::info!("no longer accepting connections, draining in-flight requests");
+            let _ = drain_tx.send(());
+        };
+        let grace = config.shutdown_grace();
+        let reflection = if config.reflection {
+            log::info!("serving grpc reflection");
+            ReflectionService::new(tasks.clone())?
+        } else {
+            ReflectionService::disabled(tasks.clone())
+        };
+        let router = server
+            .add_service(PackybaraServer::with_interceptor(packy, interceptor))
+            .add_service(HealthServer::new(health))
+            .add_service(ServerReflectionServer::new(reflection));
+        drain(router.serve_with_shutdown(addr, signal), draining, grace).await?;
+        // the services were dropped with the server, but the streams they
+        // spawned may still be running
+        tasks.stop().await;
+        pool::close(pool);
 
         Ok(())
     }
 
-    pub fn client(&self) -> &Client {
-        &self.client
+    /// Check a connection out of the pool. The connection is returned to
+    /// the pool when dropped.
+    pub async fn client(&self) -> Result<PooledClient<'_>, Status> {
+        self.pool
+            .get()
+            .await
+            .map_err(|e| status::from_pool_error(&e))
+    }
+
+    /// The caller on whose behalf the request is made
+    pub fn caller<T>(&self, request: &Request<T>) -> Caller {
+        Caller::new(auth::user(request), self.policy.clone())
+    }
+
+    // Add packages, levels, roles or platforms in a transaction of their own,
+    // on behalf of the caller. This is the body shared by the Add rpcs taking
+    // a list of names.
+    async fn add_names(
+        &self,
+        caller: &Caller,
+        kind: NameKind,
+        mut names: Vec<String>,
+        author: String,
+        comment: String,
+    ) -> Result<Response<AddReply>, Status> {
+        let author = caller.author(author);
+        validate_write(&names, kind.noun(), &author, &comment)?;
+        kind.authorize(caller, &names)?;
+
+        let mut client = self.client().await?;
+        let (tx, transaction_id) = begin(&mut client).await?;
+        let mut pbd = PackratDb::new();
+        let updates = match kind {
+            NameKind::Packages => pbd
+                .add_packages(tx)
+                .packages(&mut names)
+                .create()
+                .await
+                .into_status()?
+                .commit(&author, &comment)
+                .await
+                .into_status()?,
+            NameKind::Levels => pbd
+                .add_levels(tx)
+                .levels(&mut names)
+                .create()
+                .await
+                .into_status()?
+                .commit(&author, &comment)
+                .await
+                .into_status()?,
+            NameKind::Roles => pbd
+                .add_roles(tx)
+                .roles(&mut names)
+                .create()
+                .await
+                .into_status()?
+                .commit(&author, &comment)
+                .await
+                .into_status()?,
+            NameKind::Platforms => pbd
+                .add_platforms(tx)
+                .platforms(&mut names)
+                .create()
+                .await
+                .into_status()?
+                .commit(&author, &comment)
+                .await
+                .into_status()?,
+        };
+        let revision_id = revision_id(&client, transaction_id).await?;
+        Ok(Response::new(AddReply {
+            updates: updates as i64,
+            revision_id: revision_id as i64,
+            transaction_id,
+        }))
+    }
+
+    // Add, reorder or remove the withs of a versionpin in a transaction of
+    // their own, on behalf of the caller. This is the body shared by the
+    // Withs write rpcs.
+    async fn write_withs(
+        &self,
+        caller: &Caller,
+        change: WithsChange,
+        versionpin_id: i64,
+        withs: Vec<String>,
+        author: String,
+        comment: String,
+    ) -> Result<Response<WithsWriteReply>, Status> {
+        let versionpin_id = to_id("versionpin_id", versionpin_id)?;
+        let author = caller.author(author);
+        validate_write(&withs, "withs", &author, &comment)?;
+
+        let mut client = self.client().await?;
+        let (tx, transaction_id) = begin(&mut client).await?;
+        let mut pbd = PackratDb::new();
+        let mut changeset = pbd.changeset(tx);
+        update_withs(&mut changeset, caller, change, versionpin_id, withs).await?;
+        let withs = find_versionpin_withs(changeset.tx(), versionpin_id).await?;
+        changeset.commit(&author, &comment).await.into_status()?;
+        let revision_id = revision_id(&client, transaction_id).await?;
+        Ok(Response::new(WithsWriteReply {
+            withs,
+            revision_id: revision_id as i64,
+            transaction_id,
+        }))
     }
 }
 
@@ -70,6 +310,7 @@ impl Packybara for PackybaraService {
         &self,
         request: Request<VersionPinQueryRequest>,
     ) -> Result<Response<VersionPinQueryReply>, Status> {
+        let client = self.client().await?;
         let mut pbd = PackratDb::new();
         let msg = request.get_ref();
         let result = pbd
@@ -78,9 +319,9 @@ impl Packybara for PackybaraService {
             .role(msg.role.as_deref().unwrap_or("any"))
             .platform(msg.platform.as_deref().unwrap_or("any"))
             .site(msg.site.as_deref().unwrap_or("any"))
-            .query(self.client())
+            .query(&client)
             .await
-            .unwrap();
+            .into_status()?;
 
         let FindVersionPinsRow {
             versionpin_id,
@@ -118,125 +359,2471 @@ impl Packybara for PackybaraService {
         &self,
         request: Request<VersionPinsQueryRequest>,
     ) -> Result<Response<VersionPinsQueryReply>, Status> {
+        let query = VersionPinsQuery::from_request(request.into_inner())?;
         let mut pbd = PackratDb::new();
+        let mut results = pbd.find_all_versionpins();
+        query.configure(&mut results);
+        let client = self.client().await?;
+        let mut rows = results.query(&client).await.into_status()?;
+        let next_page_token = query.next_page_token(&mut rows);
+        let mut withs = WithsResolver::new();
+        let mut vpins = Vec::with_capacity(rows.len());
+        for row in rows {
+            vpins.push(query.to_row(&client, row, &mut withs).await?);
+        }
+        Ok(Response::new(VersionPinsQueryReply {
+            vpins,
+            next_page_token,
+        }))
+    }
 
-        let VersionPinsQueryRequest {
+    async fn get_roles(
+        &self,
+        request: Request<RolesQueryRequest>,
+    ) -> Result<Response<RolesQueryReply>, Status> {
+        let RolesQueryRequest {
+            role,
+            category,
+            order_by,
+        } = request.into_inner();
+        let order_by = parse_order_by::<OrderRoleBy>(order_by.as_deref())?;
+
+        let mut pbd = PackratDb::new();
+        let mut results = pbd.find_all_roles();
+        results
+            .role_opt(role.as_deref())
+            .category_opt(category.as_deref())
+            .order_by_opt(order_by);
+        let client = self.client().await?;
+        let roles = results
+            .query(&client)
+            .await
+            .into_status()?
+            .into_iter()
+            .map(|FindAllRolesRow { role, category }| RolesQueryRow { role, category })
+            .collect::<Vec<_>>();
+        Ok(Response::new(RolesQueryReply { roles }))
+    }
+
+    async fn get_platforms(
+        &self,
+        request: Request<PlatformsQueryRequest>,
+    ) -> Result<Response<PlatformsQueryReply>, Status> {
+        let PlatformsQueryRequest { platform, order_by } = request.into_inner();
+        let order_by = parse_order_by::<OrderPlatformBy>(order_by.as_deref())?;
+
+        let mut pbd = PackratDb::new();
+        let mut results = pbd.find_all_platforms();
+        results
+            .platform_opt(platform.as_deref())
+            .order_by_opt(order_by);
+        let client = self.client().await?;
+        let names = results
+            .query(&client)
+            .await
+            .into_status()?
+            .into_iter()
+            .map(|FindAllPlatformsRow { name }| name)
+            .collect::<Vec<_>>();
+        Ok(Response::new(PlatformsQueryReply { names }))
+    }
+
+    async fn get_sites(
+        &self,
+        request: Request<SitesQueryRequest>,
+    ) -> Result<Response<SitesQueryReply>, Status> {
+        let SitesQueryRequest { site } = request.into_inner();
+
+        let mut pbd = PackratDb::new();
+        let mut results = pbd.find_all_sites();
+        results.site_opt(site.as_deref());
+        let client = self.client().await?;
+        let names = results
+            .query(&client)
+            .await
+            .into_status()?
+            .into_iter()
+            .map(|FindAllSitesRow { name }| name)
+            .collect::<Vec<_>>();
+        Ok(Response::new(SitesQueryReply { names }))
+    }
+
+    async fn get_levels(
+        &self,
+        request: Request<LevelsQueryRequest>,
+    ) -> Result<Response<LevelsQueryReply>, Status> {
+        let LevelsQueryRequest {
+            level,
+            show,
+            depth,
+            order_by,
+        } = request.into_inner();
+        let order_by = parse_order_by::<OrderLevelBy>(order_by.as_deref())?;
+        let depth = depth
+            .map(|d| {
+                u8::try_from(d)
+                    .map_err(|_| status::invalid_argument(format!("invalid level depth {}", d)))
+            })
+            .transpose()?;
+
+        let mut pbd = PackratDb::new();
+        let mut results = pbd.find_all_levels();
+        results
+            .level_opt(level.as_deref())
+            .show_opt(show.as_deref())
+            .depth_opt(depth)
+            .order_by_opt(order_by);
+        let client = self.client().await?;
+        let levels = results
+            .query(&client)
+            .await
+            .into_status()?
+            .into_iter()
+            .map(|FindAllLevelsRow { level }| level)
+            .collect::<Vec<_>>();
+        Ok(Response::new(LevelsQueryReply { levels }))
+    }
+
+    async fn get_packages(
+        &self,
+        _request: Request<PackagesQueryRequest>,
+    ) -> Result<Response<PackagesQueryReply>, Status> {
+        let mut pbd = PackratDb::new();
+        let mut results = pbd.find_all_packages();
+        let client = self.client().await?;
+        let names = results
+            .query(&client)
+            .await
+            .into_status()?
+            .into_iter()
+            .map(|FindAllPackagesRow { name }| name)
+            .collect::<Vec<_>>();
+        Ok(Response::new(PackagesQueryReply { names }))
+    }
+
+    async fn get_distributions(
+        &self,
+        request: Request<DistributionsQueryRequest>,
+    ) -> Result<Response<DistributionsQueryReply>, Status> {
+        let DistributionsQueryRequest {
             package,
             version,
+            order_direction,
+        } = request.into_inner();
+        let order_direction = parse_order_direction(order_direction)?;
+
+        let mut pbd = PackratDb::new();
+        let mut results = pbd.find_all_distributions();
+        results
+            .package_opt(package.as_deref())
+            .version_opt(version.as_deref())
+            .order_direction_opt(order_direction.map(POrderDirection::from));
+        let client = self.client().await?;
+        let distributions = results
+            .query(&client)
+            .await
+            .into_status()?
+            .into_iter()
+            .map(to_distributions_row)
+            .collect::<Vec<_>>();
+        Ok(Response::new(DistributionsQueryReply { distributions }))
+    }
+
+    async fn get_pkg_coords(
+        &self,
+        request: Request<PkgCoordsQueryRequest>,
+    ) -> Result<Response<PkgCoordsQueryReply>, Status> {
+        let PkgCoordsQueryRequest {
+            package,
+            level,
+            role,
+            platform,
+            site,
+            order_by,
+            search_mode,
+        } = request.into_inner();
+        let (level, role, platform, site) = extract_coords(level, role, platform, site);
+        let search_mode = parse_search_mode(search_mode)?;
+        let order_by = parse_order_by::<OrderPkgCoordsBy>(order_by.as_deref())?;
+
+        let mut pbd = PackratDb::new();
+        let mut results = pbd.find_all_pkgcoords();
+        results
+            .package_opt(package.as_deref())
+            .level(level.as_str())
+            .role(role.as_str())
+            .platform(platform.as_str())
+            .site(site.as_str())
+            .search_mode(search_mode.into())
+            .order_by_opt(order_by);
+        let client = self.client().await?;
+        let pkgcoords = results
+            .query(&client)
+            .await
+            .into_status()?
+            .into_iter()
+            .map(to_pkgcoords_row)
+            .collect::<Vec<_>>();
+        Ok(Response::new(PkgCoordsQueryReply { pkgcoords }))
+    }
+
+    async fn get_pins(
+        &self,
+        request: Request<PinsQueryRequest>,
+    ) -> Result<Response<PinsQueryReply>, Status> {
+        let PinsQueryRequest {
             level,
             role,
             platform,
             site,
-            isolate_facility,
             search_mode,
             order_by,
-            order_direction,
-            full_withs,
             limit,
         } = request.into_inner();
+        let (level, role, platform, site) = extract_coords(level, role, platform, site);
+        let search_mode = parse_search_mode(search_mode)?;
+        let order_by = parse_search_attributes(&order_by)?;
+        let limit = limit.map(|limit| to_id("limit", limit)).transpose()?;
 
-        let (level, role, platform, site, mode) =
-            extract_coords(level, role, platform, site, search_mode);
-
-        let mut results = pbd.find_all_versionpins();
+        let mut pbd = PackratDb::new();
+        let mut results = pbd.find_pins();
         results
-            .some_package(package.as_deref())
-            .some_version(version.as_deref())
             .level(level.as_str())
-            .isolate_facility(isolate_facility.unwrap_or(false))
             .role(role.as_str())
             .platform(platform.as_str())
             .site(site.as_str())
-            .search_mode(
-                LtreeSearchMode::from_str(mode.as_str())
-                    .map_err(|e| Status::new(Code::Internal, format!("{}", e)))?,
-            );
-        if let Some(ref order) = order_by {
-            let orders = order
-                .split(",")
-                .map(|x| SearchAttribute::from_str(x).unwrap_or(SearchAttribute::Unknown))
-                .collect::<Vec<SearchAttribute>>();
-            results.order_by(orders);
-        }
-        if let Some(ref dir) = order_direction {
-            let direction = OrderDirection::from_str(dir);
-            if direction.is_ok() {
-                let direction = direction.unwrap();
-                results.order_direction(direction);
-            } else {
-                log::warn!("unable to apply search direction request {} to query", dir);
-            }
+            .search_mode(search_mode.into());
+        if !order_by.is_empty() {
+            results.order_by(order_by.into_iter().map(PSearchAttribute::from).collect());
         }
-        let intermediate_results = results
-            .query(self.client())
+        let client = self.client().await?;
+        let pins = results
+            .query(&client)
             .await
-            .map_err(|x| Status::new(Code::Internal, format!("{}", x)))?;
-        let mut vpins = Vec::new();
-        for result in intermediate_results {
-            let FindAllVersionPinsRow {
-                versionpin_id,
-                distribution_id,
-                pkgcoord_id,
-                distribution,
-                coords:
-                    PCoords {
-                        role,
-                        level,
-                        platform,
-                        site,
+            .into_status()?
+            .into_iter()
+            .take(limit.map_or(usize::max_value(), |limit| limit.max(0) as usize))
+            .map(|FindPinsRow { id, coords }| PinsQueryRow {
+                id: id as i64,
+                coords: to_coords(&coords),
+            })
+            .collect::<Vec<_>>();
+        Ok(Response::new(PinsQueryReply { pins }))
+    }
+
+    async fn get_withs(
+        &self,
+        request: Request<WithsQueryRequest>,
+    ) -> Result<Response<WithsQueryReply>, Status> {
+        let WithsQueryRequest {
+            package,
+            level,
+            role,
+            platform,
+            site,
+        } = request.into_inner();
+
+        let mut pbd = PackratDb::new();
+        let mut results = pbd.find_withs(package.as_str());
+        results
+            .level(level.as_deref().unwrap_or("facility"))
+            .role(role.as_deref().unwrap_or("any"))
+            .platform(platform.as_deref().unwrap_or("any"))
+            .site(site.as_deref().unwrap_or("any"));
+        let client = self.client().await?;
+        let withs = results
+            .query(&client)
+            .await
+            .into_status()?
+            .into_iter()
+            .map(
+                |FindWithsRow {
+                     versionpin_id,
+                     distribution,
+                     coords,
+                 }| WithsQueryRow {
+                    versionpin_id: versionpin_id as i64,
+                    distribution: distribution.to_string(),
+                    coords: to_coords(&coords),
+                },
+            )
+            .collect::<Vec<_>>();
+        Ok(Response::new(WithsQueryReply { withs }))
+    }
+
+    async fn get_version_pin_withs(
+        &self,
+        request: Request<VersionPinWithsQueryRequest>,
+    ) -> Result<Response<VersionPinWithsQueryReply>, Status> {
+        let VersionPinWithsQueryRequest { versionpin_id } = request.into_inner();
+        let versionpin_id = to_id("versionpin_id", versionpin_id)?;
+
+        let client = self.client().await?;
+        let mut pbd = PackratDb::new();
+        let mut results = pbd.find_versionpins_by_ids();
+        results.versionpin_ids(&[versionpin_id]);
+        let versionpin = results
+            .query(&client)
+            .await
+            .into_status()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                Status::new(
+                    Code::NotFound,
+                    format!("no versionpin with id {}", versionpin_id),
+                )
+            })?;
+        let mut pbd = PackratDb::new();
+        let mut results = pbd.find_versionpin_withs(versionpin_id);
+        let rows = results
+            .query(&client)
+            .await
+            .into_status()?
+            .into_iter()
+            .map(to_versionpin_withs_row)
+            .collect::<Vec<_>>();
+        // the withs resolve at the coords of the versionpin they belong to
+        let names = rows.iter().map(|row| row.with.clone()).collect::<Vec<_>>();
+        let resolved = WithsResolver::new()
+            .resolve_rows(&client, &names, &to_coords(&versionpin.coords))
+            .await?;
+        let withs = rows
+            .into_iter()
+            .zip(resolved)
+            .map(|(row, resolved)| VersionPinWithsQueryRow {
+                resolved: Some(resolved),
+                ..row
+            })
+            .collect::<Vec<_>>();
+        Ok(Response::new(VersionPinWithsQueryReply { withs }))
+    }
+
+    async fn get_revisions(
+        &self,
+        request: Request<RevisionsQueryRequest>,
+    ) -> Result<Response<RevisionsQueryReply>, Status> {
+        let RevisionsQueryRequest {
+            id,
+            transaction_id,
+            author,
+            order_by,
+            limit,
+            order_direction,
+        } = request.into_inner();
+        let id = id.map(|id| to_id("id", id)).transpose()?;
+        let limit = limit.map(|limit| to_id("limit", limit)).transpose()?;
+        let order_by = parse_order_by::<OrderRevisionBy>(order_by.as_deref())?;
+        let order_direction = parse_order_direction(order_direction)?;
+
+        let mut pbd = PackratDb::new();
+        let mut results = pbd.find_all_revisions();
+        results
+            .id_opt(id)
+            .transaction_id_opt(transaction_id.map(|x| x as LongIdType))
+            .author_opt(author.as_deref())
+            .order_by_opt(order_by)
+            .order_direction_opt(order_direction.map(POrderDirection::from))
+            .limit_opt(limit);
+        let client = self.client().await?;
+        let revisions = results
+            .query(&client)
+            .await
+            .into_status()?
+            .into_iter()
+            .map(
+                |FindAllRevisionsRow {
+                     id,
+                     transaction_id,
+                     author,
+                     comment,
+                     datetime,
+                 }| RevisionsQueryRow {
+                    id: id as i64,
+                    transaction_id: transaction_id as i64,
+                    author,
+                    comment,
+                    datetime: to_timestamp(&datetime),
+                },
+            )
+            .collect::<Vec<_>>();
+        Ok(Response::new(RevisionsQueryReply { revisions }))
+    }
+
+    async fn get_changes(
+        &self,
+        request: Request<ChangesQueryRequest>,
+    ) -> Result<Response<ChangesQueryReply>, Status> {
+        let ChangesQueryRequest { transaction_id } = request.into_inner();
+
+        let mut pbd = PackratDb::new();
+        let mut results = pbd.find_all_changes();
+        results.transaction_id(transaction_id as LongIdType);
+        let client = self.client().await?;
+        let changes = results
+            .query(&client)
+            .await
+            .into_status()?
+            .into_iter()
+            .map(
+                |FindAllChangesRow {
+                     id,
+                     transaction_id,
+                     action,
+                     level,
+                     role,
+                     platform,
+                     site,
+                     package,
+                     old,
+                     new,
+                 }| ChangesQueryRow {
+                    id: id as i64,
+                    transaction_id: transaction_id as i64,
+                    action: to_change_action(&action) as i32,
+                    coords: Coords {
+                        level: level.to_string(),
+                        role: role.to_string(),
+                        platform: platform.to_string(),
+                        site: site.to_string(),
                     },
-                withs,
-            } = result;
+                    package,
+                    old: old.to_string(),
+                    new: new.to_string(),
+                },
+            )
+            .collect::<Vec<_>>();
+        Ok(Response::new(ChangesQueryReply { changes }))
+    }
 
-            let coords = Coords {
-                level: level.to_string(),
-                role: role.to_string(),
-                platform: platform.to_string(),
-                site: site.to_string(),
-            };
+    async fn add_packages(
+        &self,
+        request: Request<PackagesAddRequest>,
+    ) -> Result<Response<AddReply>, Status> {
+        let caller = self.caller(&request);
+        let PackagesAddRequest {
+            names,
+            author,
+            comment,
+        } = request.into_inner();
+        self.add_names(&caller, NameKind::Packages, names, author, comment)
+            .await
+    }
 
-            let reply = VersionPinsQueryRow {
-                versionpin_id: versionpin_id as i64,
-                distribution_id: distribution_id as i64,
-                pkgcoord_id: pkgcoord_id as i64,
-                distribution: distribution.to_string(),
-                coords,
-                withs: withs
-                    .unwrap_or(Vec::new())
-                    .iter()
-                    .map(|x| x.to_string())
-                    .collect::<Vec<_>>(),
-            };
-            vpins.push(reply);
+    async fn add_levels(
+        &self,
+        request: Request<LevelsAddRequest>,
+    ) -> Result<Response<AddReply>, Status> {
+        let caller = self.caller(&request);
+        let LevelsAddRequest {
+            names,
+            author,
+            comment,
+        } = request.into_inner();
+        self.add_names(&caller, NameKind::Levels, names, author, comment)
+            .await
+    }
+
+    async fn add_roles(
+        &self,
+        request: Request<RolesAddRequest>,
+    ) -> Result<Response<AddReply>, Status> {
+        let caller = self.caller(&request);
+        let RolesAddRequest {
+            names,
+            author,
+            comment,
+        } = request.into_inner();
+        self.add_names(&caller, NameKind::Roles, names, author, comment)
+            .await
+    }
+
+    async fn add_platforms(
+        &self,
+        request: Request<PlatformsAddRequest>,
+    ) -> Result<Response<AddReply>, Status> {
+        let caller = self.caller(&request);
+        let PlatformsAddRequest {
+            names,
+            author,
+            comment,
+        } = request.into_inner();
+        self.add_names(&caller, NameKind::Platforms, names, author, comment)
+            .await
+    }
+
+    async fn add_version_pins(
+        &self,
+        request: Request<VersionPinsAddRequest>,
+    ) -> Result<Response<VersionPinsWriteReply>, Status> {
+        let caller = self.caller(&request);
+        let VersionPinsAddRequest {
+            package,
+            version,
+            levels,
+            roles,
+            platforms,
+            sites,
+            author,
+            comment,
+        } = request.into_inner();
+        let author = caller.author(author);
+        validate_revision(&author, &comment)?;
+        let mut levels = or_default(levels, "facility");
+        caller.authorize_levels(&levels)?;
+        let mut roles = or_default(roles, "any");
+        let mut platforms = or_default(platforms, "any");
+        let mut sites = or_default(sites, "any");
+
+        let mut client = self.client().await?;
+        let (tx, transaction_id) = begin(&mut client).await?;
+        let mut pbd = PackratDb::new();
+        let mut changeset = pbd.changeset(tx);
+        let ids = changeset
+            .add_versionpins(
+                package,
+                version,
+                &mut levels,
+                &mut roles,
+                &mut platforms,
+                &mut sites,
+            )
+            .await
+            .into_status()?;
+        let vpins = find_versionpins_by_ids(changeset.tx(), &ids)
+            .await?
+            .into_iter()
+            .map(to_versionpins_row)
+            .collect::<Vec<_>>();
+        changeset.commit(&author, &comment).await.into_status()?;
+        let revision_id = revision_id(&client, transaction_id).await?;
+        Ok(Response::new(VersionPinsWriteReply {
+            vpins,
+            revision_id: revision_id as i64,
+            transaction_id,
+        }))
+    }
+
+    async fn set_version_pins(
+        &self,
+        request: Request<VersionPinsSetRequest>,
+    ) -> Result<Response<VersionPinsWriteReply>, Status> {
+        let caller = self.caller(&request);
+        let VersionPinsSetRequest {
+            changes,
+            author,
+            comment,
+        } = request.into_inner();
+        if changes.is_empty() {
+            return Err(status::invalid_argument("no changes supplied"));
+        }
+        let author = caller.author(author);
+        validate_revision(&author, &comment)?;
+
+        let mut client = self.client().await?;
+        let (tx, transaction_id) = begin(&mut client).await?;
+        let mut resolved = Vec::with_capacity(changes.len());
+        for change in changes {
+            resolved.push(resolve_versionpin_change(&tx, change).await?);
         }
-        Ok(Response::new(VersionPinsQueryReply { vpins }))
+        let ids = resolved.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+        caller.authorize_levels(versionpin_levels(&tx, &ids).await?)?;
+
+        let mut pbd = PackratDb::new();
+        let mut changeset = pbd.changeset(tx);
+        for (versionpin_id, distribution_id) in &resolved {
+            changeset
+                .update_versionpin(*versionpin_id, *distribution_id)
+                .await
+                .into_status()?;
+        }
+        let vpins = find_versionpins_by_ids(changeset.tx(), &ids)
+            .await?
+            .into_iter()
+            .map(to_versionpins_row)
+            .collect::<Vec<_>>();
+        changeset.commit(&author, &comment).await.into_status()?;
+        let revision_id = revision_id(&client, transaction_id).await?;
+        Ok(Response::new(VersionPinsWriteReply {
+            vpins,
+            revision_id: revision_id as i64,
+            transaction_id,
+        }))
     }
-}
 
-/// Build a tuple of coordinates given a their components as Options.
-/// This takes care of default initialization
-///
-/// # Arguments
-/// * `level` - A reference to an Option wrapped string
-/// * `role` - A reference to an Option wrapped string
-/// * `platform` - A reference to an Option wrapped string
-/// * `site` - A reference to an Option wrapped String
-/// * `mode` - A reference to an Option wrapped string
-///
-/// # Returns
-/// * tuple of strings (level, role, platform, site, mode)
-pub fn extract_coords(
-    level: Option<String>,
-    role: Option<String>,
-    platform: Option<String>,
-    site: Option<String>,
-    mode: Option<String>,
-) -> (String, String, String, String, String) {
-    let r = role.unwrap_or("any".to_string());
-    let l = level.unwrap_or("facility".to_string());
-    let p = platform.unwrap_or("any".to_string());
-    let s = site.unwrap_or("any".to_string());
-    let m = mode.unwrap_or("ancestor".to_string());
+    async fn add_withs(
+        &self,
+        request: Request<WithsAddRequest>,
+    ) -> Result<Response<WithsWriteReply>, Status> {
+        let caller = self.caller(&request);
+        let WithsAddRequest {
+            versionpin_id,
+            withs,
+            author,
+            comment,
+        } = request.into_inner();
+        self.write_withs(
+            &caller,
+            WithsChange::Add,
+            versionpin_id,
+            withs,
+            author,
+            comment,
+        )
+        .await
+    }
+
+    async fn reorder_withs(
+        &self,
+        request: Request<WithsReorderRequest>,
+    ) -> Result<Response<WithsWriteReply>, Status> {
+        let caller = self.caller(&request);
+        let WithsReorderRequest {
+            versionpin_id,
+            withs,
+            author,
+            comment,
+        } = request.into_inner();
+        self.write_withs(
+            &caller,
+            WithsChange::Reorder,
+            versionpin_id,
+            withs,
+            author,
+            comment,
+        )
+        .await
+    }
+
+    async fn remove_withs(
+        &self,
+        request: Request<WithsRemoveRequest>,
+    ) -> Result<Response<WithsWriteReply>, Status> {
+        let caller = self.caller(&request);
+        let WithsRemoveRequest {
+            versionpin_id,
+            withs,
+            author,
+            comment,
+        } = request.into_inner();
+        self.write_withs(
+            &caller,
+            WithsChange::Remove,
+            versionpin_id,
+            withs,
+            author,
+            comment,
+        )
+        .await
+    }
+
+    async fn delete_version_pins(
+        &self,
+        request: Request<VersionPinsDeleteRequest>,
+    ) -> Result<Response<VersionPinsDeleteReply>, Status> {
+        let caller = self.caller(&request);
+        let VersionPinsDeleteRequest {
+            ids,
+            author,
+            comment,
+            dry_run,
+        } = request.into_inner();
+        let ids = to_ids("ids", &ids)?;
+        let author = caller.author(author);
+        validate_delete(&ids, "versionpins", &author, &comment)?;
+
+        let mut client = self.client().await?;
+        let (tx, transaction_id) = begin(&mut client).await?;
+        let mut pbd = PackratDb::new();
+        let mut changeset = pbd.changeset(tx);
+        let (_, vpins) = delete_versionpins(&mut changeset, &caller, ids).await?;
+        if dry_run.unwrap_or(false) {
+            // dropping the changeset rolls back its transaction
+            return Ok(Response::new(VersionPinsDeleteReply {
+                vpins,
+                revision_id: None,
+                transaction_id: None,
+            }));
+        }
+        changeset.commit(&author, &comment).await.into_status()?;
+        let revision_id = revision_id(&client, transaction_id).await?;
+        Ok(Response::new(VersionPinsDeleteReply {
+            vpins,
+            revision_id: Some(revision_id as i64),
+            transaction_id: Some(transaction_id),
+        }))
+    }
 
-    (l, r, p, s, m)
+    async fn delete_withs(
+        &self,
+        request: Request<WithsDeleteRequest>,
+    ) -> Result<Response<WithsDeleteReply>, Status> {
+        let caller = self.caller(&request);
+        let WithsDeleteRequest {
+            versionpin_id,
+            ids,
+            author,
+            comment,
+            dry_run,
+        } = request.into_inner();
+        let versionpin_id = to_id("versionpin_id", versionpin_id)?;
+        let ids = to_ids("ids", &ids)?;
+        let author = caller.author(author);
+        validate_delete(&ids, "withs", &author, &comment)?;
+
+        let mut client = self.client().await?;
+        let (tx, transaction_id) = begin(&mut client).await?;
+        let mut pbd = PackratDb::new();
+        let mut changeset = pbd.changeset(tx);
+        let (_, withs) = delete_withs(&mut changeset, &caller, versionpin_id, ids).await?;
+        if dry_run.unwrap_or(false) {
+            // dropping the changeset rolls back its transaction
+            return Ok(Response::new(WithsDeleteReply {
+                withs,
+                revision_id: None,
+                transaction_id: None,
+            }));
+        }
+        changeset.commit(&author, &comment).await.into_status()?;
+        let revision_id = revision_id(&client, transaction_id).await?;
+        Ok(Response::new(WithsDeleteReply {
+            withs,
+            revision_id: Some(revision_id as i64),
+            transaction_id: Some(transaction_id),
+        }))
+    }
+
+    async fn delete_pkg_coords(
+        &self,
+        request: Request<PkgCoordsDeleteRequest>,
+    ) -> Result<Response<PkgCoordsDeleteReply>, Status> {
+        let caller = self.caller(&request);
+        let PkgCoordsDeleteRequest {
+            ids,
+            author,
+            comment,
+            dry_run,
+        } = request.into_inner();
+        let ids = to_ids("ids", &ids)?;
+        let author = caller.author(author);
+        validate_delete(&ids, "pkgcoords", &author, &comment)?;
+
+        let mut client = self.client().await?;
+        let (tx, transaction_id) = begin(&mut client).await?;
+        let mut pbd = PackratDb::new();
+        let mut changeset = pbd.changeset(tx);
+        let (_, pkgcoords) = delete_pkgcoords(&mut changeset, &caller, ids).await?;
+        if dry_run.unwrap_or(false) {
+            // dropping the changeset rolls back its transaction
+            return Ok(Response::new(PkgCoordsDeleteReply {
+                pkgcoords,
+                revision_id: None,
+                transaction_id: None,
+            }));
+        }
+        changeset.commit(&author, &comment).await.into_status()?;
+        let revision_id = revision_id(&client, transaction_id).await?;
+        Ok(Response::new(PkgCoordsDeleteReply {
+            pkgcoords,
+            revision_id: Some(revision_id as i64),
+            transaction_id: Some(transaction_id),
+        }))
+    }
+
+    async fn delete_distributions(
+        &self,
+        request: Request<DistributionsDeleteRequest>,
+    ) -> Result<Response<DistributionsDeleteReply>, Status> {
+        let caller = self.caller(&request);
+        let DistributionsDeleteRequest {
+            ids,
+            author,
+            comment,
+            dry_run,
+        } = request.into_inner();
+        let ids = to_ids("ids", &ids)?;
+        let author = caller.author(author);
+        validate_delete(&ids, "distributions", &author, &comment)?;
+
+        let mut client = self.client().await?;
+        let (tx, transaction_id) = begin(&mut client).await?;
+        let mut pbd = PackratDb::new();
+        let mut changeset = pbd.changeset(tx);
+        let (_, distributions) = delete_distributions(&mut changeset, &caller, ids).await?;
+        if dry_run.unwrap_or(false) {
+            // dropping the changeset rolls back its transaction
+            return Ok(Response::new(DistributionsDeleteReply {
+                distributions,
+                revision_id: None,
+                transaction_id: None,
+            }));
+        }
+        changeset.commit(&author, &comment).await.into_status()?;
+        let revision_id = revision_id(&client, transaction_id).await?;
+        Ok(Response::new(DistributionsDeleteReply {
+            distributions,
+            revision_id: Some(revision_id as i64),
+            transaction_id: Some(transaction_id),
+        }))
+    }
+
+    async fn apply_changeset(
+        &self,
+        request: Request<ChangesetRequest>,
+    ) -> Result<Response<ChangesetReply>, Status> {
+        let caller = self.caller(&request);
+        let ChangesetRequest {
+            ops,
+            author,
+            comment,
+        } = request.into_inner();
+        if ops.is_empty() {
+            return Err(status::invalid_argument("no ops supplied"));
+        }
+        let author = caller.author(author);
+        validate_revision(&author, &comment)?;
+
+        // each op is resolved within the transaction, so that it sees the
+        // writes of the ops before it
+        let mut client = self.client().await?;
+        let (tx, transaction_id) = begin(&mut client).await?;
+        let mut pbd = PackratDb::new();
+        let mut changeset = pbd.changeset(tx);
+        let mut results = Vec::with_capacity(ops.len());
+        for (index, ChangesetOp { op }) in ops.into_iter().enumerate() {
+            let op =
+                op.ok_or_else(|| status::invalid_argument(format!("op {} is empty", index)))?;
+            let action = ChangesetAction::resolve(changeset.tx(), op)
+                .await
+                .map_err(|e| in_op(index, e))?;
+            action
+                .authorize(changeset.tx(), &caller)
+                .await
+                .map_err(|e| in_op(index, e))?;
+            let rows = action
+                .apply(&mut changeset, &caller)
+                .await
+                .map_err(|e| in_op(index, e))?;
+            results.push(ChangesetOpResult {
+                index: index as u32,
+                rows: rows as i64,
+            });
+        }
+        changeset.commit(&author, &comment).await.into_status()?;
+        let revision_id = revision_id(&client, transaction_id).await?;
+        Ok(Response::new(ChangesetReply {
+            results,
+            revision_id: revision_id as i64,
+            transaction_id,
+        }))
+    }
+
+    type ExportPackagesXmlStream = mpsc::Receiver<Result<PackagesXmlChunk, Status>>;
+
+    // The document is streamed as packybara's writer renders it. The writer
+    // runs on a blocking thread, and waits for the client to take each chunk
+    // once STREAM_BUFFER_SIZE chunks are queued. It stops once the client
+    // hangs up, or the server drops the stream as it shuts down.
+    async fn export_packages_xml(
+        &self,
+        request: Request<PackagesXmlRequest>,
+    ) -> Result<Response<Self::ExportPackagesXmlStream>, Status> {
+        let PackagesXmlRequest {
+            show,
+            level,
+            role,
+            platform,
+            site,
+        } = request.into_inner();
+        if show.trim().is_empty() {
+            return Err(status::invalid_argument("a show is required"));
+        }
+        let level = level.unwrap_or_else(|| show.clone());
+        if level != show && !level.starts_with(&format!("{}.", show)) {
+            return Err(status::invalid_argument(format!(
+                "level {} is not within show {}",
+                level, show
+            )));
+        }
+        let (level, role, platform, site) = extract_coords(Some(level), role, platform, site);
+        let coords = Coords {
+            level,
+            role,
+            platform,
+            site,
+        };
+
+        let pool = self.pool.clone();
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER_SIZE);
+        self.tasks.spawn(async move {
+            let handle = Handle::current();
+            let writing = tokio::task::spawn_blocking(move || {
+                write_packages_xml(handle, pool, show, coords, tx)
+            });
+            if let Err(e) = writing.await {
+                log::error!("the packages.xml writer panicked: {}", e);
+            }
+        })?;
+        Ok(Response::new(rx))
+    }
+
+    type StreamVersionPinsStream = mpsc::Receiver<Result<VersionPinsQueryRow, Status>>;
+
+    async fn stream_version_pins(
+        &self,
+        request: Request<VersionPinsQueryRequest>,
+    ) -> Result<Response<Self::StreamVersionPinsStream>, Status> {
+        // parse the request up front, so that invalid arguments fail the call
+        // rather than the stream
+        let query = VersionPinsQuery::from_request(request.into_inner())?;
+        if query.is_paged() {
+            return Err(status::invalid_argument(
+                "page_size and page_token are not supported by a stream",
+            ));
+        }
+        let pool = self.pool.clone();
+        let (mut tx, rx) = mpsc::channel(STREAM_BUFFER_SIZE);
+        self.tasks.spawn(async move {
+            if let Err(status) = query.stream(&pool, &mut tx).await {
+                // the client may already have hung up
+                let _ = tx.send(Err(status)).await;
+            }
+        })?;
+        Ok(Response::new(rx))
+    }
+}
+
+// Render the packages.xml of a show with packybara's writer, sending the
+// document in chunks as it is written. This blocks, both on the database and
+// on the client, so must be run on a blocking thread.
+fn write_packages_xml(
+    handle: Handle,
+    pool: ConnectionPool,
+    show: String,
+    coords: Coords,
+    mut tx: mpsc::Sender<Result<PackagesXmlChunk, Status>>,
+) {
+    let mut chunks = tx.clone();
+    let mut writer = ChunkWriter::new(move |data| {
+        // the writer is called from within the runtime's block_on, which may
+        // not be nested, so the send is driven by an executor of its own
+        futures::executor::block_on(chunks.send(Ok(PackagesXmlChunk { data }))).is_ok()
+    });
+    let written = handle.block_on(async {
+        let client = pool.get().await.map_err(|e| status::from_pool_error(&e))?;
+        write_xml(
+            &client,
+            &show,
+            &coords.level,
+            &coords.role,
+            &coords.platform,
+            &coords.site,
+            &mut writer,
+        )
+        .await
+        .into_status()
+    });
+    let result = written.and_then(|_| {
+        writer
+            .flush()
+            .map_err(|_| Status::new(Code::Cancelled, "the client hung up"))
+    });
+    if let Err(status) = result {
+        // the client may already have hung up
+        if futures::executor::block_on(tx.send(Err(status))).is_err() {
+            log::debug!("client hung up before the packages.xml stream completed");
+        }
+    }
+}
+
+// Serve until shutdown begins, then allow in-flight requests the grace
+// period to complete before abandoning them.
+async fn drain<S>(
+    serve: S,
+    draining: oneshot::Receiver<()>,
+    grace: Duration,
+) -> Result<(), tonic::transport::Error>
+where
+    S: Future<Output = Result<(), tonic::transport::Error>>,
+{
+    tokio::pin!(serve);
+    tokio::select! {
+        result = &mut serve => return result,
+        _ = draining => {}
+    }
+    match tokio::time::timeout(grace, serve).await {
+        Ok(result) => result,
+        Err(_) => {
+            log::warn!(
+                "abandoning in-flight requests after a grace period of {}s",
+                grace.as_secs()
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Parse a comma separated list of attributes to order a query by. An
+/// unrecognized attribute results in an InvalidArgument Status.
+///
+/// # Arguments
+/// * `order_by` - Option wrapped, comma separated list of attributes
+///
+/// # Returns
+/// * Result
+/// - Ok - Option wrapped vector of attributes
+/// - Err - Status
+pub fn parse_order_by<T>(order_by: Option<&str>) -> Result<Option<Vec<T>>, Status>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    order_by
+        .map(|order| {
+            order
+                .split(",")
+                .map(|x| {
+                    T::from_str(x.trim()).map_err(|e| {
+                        status::invalid_argument(format!("invalid order_by '{}': {}", x, e))
+                    })
+                })
+                .collect::<Result<Vec<T>, Status>>()
+        })
+        .transpose()
+}
+
+/// Build a tuple of coordinates given a their components as Options.
+/// This takes care of default initialization
+///
+/// # Arguments
+/// * `level` - A reference to an Option wrapped string
+/// * `role` - A reference to an Option wrapped string
+/// * `platform` - A reference to an Option wrapped string
+/// * `site` - A reference to an Option wrapped String
+///
+/// # Returns
+/// * tuple of strings (level, role, platform, site)
+pub fn extract_coords(
+    level: Option<String>,
+    role: Option<String>,
+    platform: Option<String>,
+    site: Option<String>,
+) -> (String, String, String, String) {
+    let r = role.unwrap_or("any".to_string());
+    let l = level.unwrap_or("facility".to_string());
+    let p = platform.unwrap_or("any".to_string());
+    let s = site.unwrap_or("any".to_string());
+
+    (l, r, p, s)
+}
+
+/// Decode the direction in which to order a query. An unrecognized direction
+/// results in an InvalidArgument Status.
+///
+/// # Arguments
+/// * `order_direction` - Option wrapped OrderDirection, as sent on the wire
+///
+/// # Returns
+/// * Result
+/// - Ok - Option wrapped OrderDirection
+/// - Err - Status
+pub fn parse_order_direction(
+    order_direction: Option<i32>,
+) -> Result<Option<OrderDirection>, Status> {
+    order_direction
+        .map(|dir| decode_enum("order_direction", dir, OrderDirection::from_i32))
+        .transpose()
+}
+
+/// Decode the attributes to order a query by. An unrecognized attribute
+/// results in an InvalidArgument Status.
+///
+/// # Arguments
+/// * `order_by` - SearchAttributes, as sent on the wire
+///
+/// # Returns
+/// * Result
+/// - Ok - vector of SearchAttributes, empty if none were supplied
+/// - Err - Status
+pub fn parse_search_attributes(order_by: &[i32]) -> Result<Vec<SearchAttribute>, Status> {
+    order_by
+        .iter()
+        .map(|attr| decode_enum("order_by", *attr, SearchAttribute::from_i32))
+        .collect()
+}
+
+/// Convert an id received in a request into an IdType. An id which is out of
+/// range results in an InvalidArgument Status.
+pub fn to_id(field: &str, id: i64) -> Result<IdType, Status> {
+    IdType::try_from(id)
+        .map_err(|_| status::invalid_argument(format!("{} {} is out of range", field, id)))
+}
+
+/// Convert a list of ids received in a request into IdTypes. Any id which is
+/// out of range results in an InvalidArgument Status.
+pub fn to_ids(field: &str, ids: &[i64]) -> Result<Vec<IdType>, Status> {
+    ids.iter().map(|id| to_id(field, *id)).collect()
+}
+
+/// Decode the ltree search mode (ancestor, exact, or descendant), defaulting
+/// to ancestor. An unrecognized mode results in an InvalidArgument Status.
+pub fn parse_search_mode(mode: Option<i32>) -> Result<LtreeSearchMode, Status> {
+    mode.map(|mode| decode_enum("search_mode", mode, LtreeSearchMode::from_i32))
+        .unwrap_or(Ok(LtreeSearchMode::Ancestor))
+}
+
+// Decode an enum sent on the wire as an i32, rejecting unrecognized values
+fn decode_enum<T>(field: &str, value: i32, decode: fn(i32) -> Option<T>) -> Result<T, Status> {
+    decode(value).ok_or_else(|| status::invalid_argument(format!("invalid {} {}", field, value)))
+}
+
+/// Convert packybara Coords into their protobuf representation
+pub fn to_coords(coords: &PCoords) -> Coords {
+    let PCoords {
+        role,
+        level,
+        platform,
+        site,
+    } = coords;
+    Coords {
+        level: level.to_string(),
+        role: role.to_string(),
+        platform: platform.to_string(),
+        site: site.to_string(),
+    }
+}
+
+/// Convert a packybara timestamp into its protobuf representation
+pub fn to_timestamp(datetime: &DateTime<Local>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: datetime.timestamp(),
+        nanos: datetime.timestamp_subsec_nanos() as i32,
+    }
+}
+
+/// Convert a packybara ChangeAction into its protobuf representation
+pub fn to_change_action(action: &PChangeAction) -> ChangeAction {
+    match action {
+        PChangeAction::Insert => ChangeAction::Insert,
+        PChangeAction::Update => ChangeAction::Update,
+        PChangeAction::Delete => ChangeAction::Delete,
+    }
+}
+
+/// Begin a transaction on the supplied connection, returning it along with
+/// its transaction id. The id is the one packybara records in the revision
+/// created when the transaction is committed.
+pub async fn begin(client: &mut Client) -> Result<(Transaction<'_>, LongIdType), Status> {
+    let tx = client.transaction().await.into_status()?;
+    let row = tx
+        .query_one("SELECT txid_current()", &[])
+        .await
+        .into_status()?;
+    Ok((tx, row.get(0)))
+}
+
+/// Retrieve the id of the revision recorded by a committed transaction.
+pub async fn revision_id(client: &Client, transaction_id: LongIdType) -> Result<IdType, Status> {
+    let mut pbd = PackratDb::new();
+    let mut results = pbd.find_all_revisions();
+    results.transaction_id_opt(Some(transaction_id));
+    results
+        .query(client)
+        .await
+        .into_status()?
+        .into_iter()
+        .next()
+        .map(|FindAllRevisionsRow { id, .. }| id)
+        .ok_or_else(|| {
+            Status::new(
+                Code::Internal,
+                format!("no revision recorded for transaction {}", transaction_id),
+            )
+        })
+}
+
+/// The entities which are added by name alone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameKind {
+    Packages,
+    Levels,
+    Roles,
+    Platforms,
+}
+
+impl NameKind {
+    /// The plural noun used to describe the entities in messages
+    pub fn noun(self) -> &'static str {
+        match self {
+            NameKind::Packages => "packages",
+            NameKind::Levels => "levels",
+            NameKind::Roles => "roles",
+            NameKind::Platforms => "platforms",
+        }
+    }
+
+    /// Verify that the caller may add the named entities. Levels are
+    /// authorized individually, everything else is a global change.
+    pub fn authorize(self, caller: &Caller, names: &[String]) -> Result<(), Status> {
+        match self {
+            NameKind::Levels => caller.authorize_levels(names),
+            _ => caller.authorize(Scope::Global),
+        }
+    }
+}
+
+/// Validate the arguments common to requests which add named entities.
+/// Each request must name at least one entity and supply an author and comment.
+pub fn validate_write(
+    names: &[String],
+    noun: &str,
+    author: &str,
+    comment: &str,
+) -> Result<(), Status> {
+    if names.is_empty() {
+        return Err(status::invalid_argument(format!("no {} supplied", noun)));
+    }
+    validate_revision(author, comment)
+}
+
+/// Validate the arguments common to requests which delete rows by id.
+pub fn validate_delete(
+    ids: &[IdType],
+    noun: &str,
+    author: &str,
+    comment: &str,
+) -> Result<(), Status> {
+    if ids.is_empty() {
+        return Err(status::invalid_argument(format!("no {} supplied", noun)));
+    }
+    validate_revision(author, comment)
+}
+
+/// Validate the author and comment recorded in the revision created by a
+/// request which modifies the database.
+pub fn validate_revision(author: &str, comment: &str) -> Result<(), Status> {
+    if author.trim().is_empty() {
+        return Err(status::invalid_argument("an author is required"));
+    }
+    if comment.trim().is_empty() {
+        return Err(status::invalid_argument("a comment is required"));
+    }
+    Ok(())
+}
+
+// Substitute a single default value for an empty list of coordinates
+fn or_default(values: Vec<String>, default: &str) -> Vec<String> {
+    if values.is_empty() {
+        vec![default.to_string()]
+    } else {
+        values
+    }
+}
+
+/// Convert a FindAllVersionPinsRow into its protobuf representation
+pub fn to_versionpins_row(row: FindAllVersionPinsRow) -> VersionPinsQueryRow {
+    let FindAllVersionPinsRow {
+        versionpin_id,
+        distribution_id,
+        pkgcoord_id,
+        distribution,
+        coords,
+        withs,
+    } = row;
+    VersionPinsQueryRow {
+        versionpin_id: versionpin_id as i64,
+        distribution_id: distribution_id as i64,
+        pkgcoord_id: pkgcoord_id as i64,
+        distribution: distribution.to_string(),
+        coords: to_coords(&coords),
+        withs: withs
+            .unwrap_or(Vec::new())
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>(),
+    }
+}
+
+/// Retrieve the supplied versionpins, within a transaction. Ids which do not
+/// identify a versionpin are ignored.
+pub async fn find_versionpins_by_ids(
+    tx: &Transaction<'_>,
+    ids: &[IdType],
+) -> Result<Vec<FindAllVersionPinsRow>, Status> {
+    let mut pbd = PackratDb::new();
+    let mut results = pbd.find_versionpins_by_ids();
+    results.versionpin_ids(ids);
+    results.query_tx(tx).await.into_status()
+}
+
+/// Retrieve the levels of the supplied versionpins, within a transaction.
+/// Ids which do not identify a versionpin are ignored.
+pub async fn versionpin_levels(
+    tx: &Transaction<'_>,
+    ids: &[IdType],
+) -> Result<Vec<String>, Status> {
+    let levels = find_versionpins_by_ids(tx, ids)
+        .await?
+        .into_iter()
+        .map(|row| row.coords.level.to_string())
+        .collect::<Vec<_>>();
+    Ok(levels)
+}
+
+/// Resolve a VersionPinChange into the id of the versionpin to update and the
+/// id of the distribution it should resolve to, within a transaction. The
+/// distribution must be of the package the versionpin pins, which is looked up
+/// when the change identifies the versionpin by id.
+pub async fn resolve_versionpin_change(
+    tx: &Transaction<'_>,
+    change: VersionPinChange,
+) -> Result<(IdType, IdType), Status> {
+    let VersionPinChange {
+        versionpin_id,
+        package,
+        coords,
+        distribution_id,
+        version,
+    } = change;
+    let versionpin_id =
+        match (versionpin_id, package.as_deref(), coords) {
+            (Some(id), _, None) => to_id("versionpin_id", id)?,
+            (None, Some(package), Some(coords)) => find_versionpin_id(tx, package, &coords).await?,
+            _ => return Err(status::invalid_argument(
+                "a change must identify its versionpin by versionpin_id, or by package and coords",
+            )),
+        };
+    let versionpin = find_versionpins_by_ids(tx, &[versionpin_id])
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            Status::new(
+                Code::NotFound,
+                format!("no versionpin with id {}", versionpin_id),
+            )
+        })?;
+    let pinned = versionpin.distribution.package().to_string();
+    if let Some(package) = package {
+        if package != pinned {
+            return Err(status::invalid_argument(format!(
+                "versionpin {} pins {}, not {}",
+                versionpin_id, pinned, package
+            )));
+        }
+    }
+    let distribution_id = match (distribution_id, version.as_deref()) {
+        (Some(id), None) => {
+            let distribution_id = to_id("distribution_id", id)?;
+            let distribution = find_distributions_by_ids(tx, &[distribution_id])
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| {
+                    Status::new(
+                        Code::NotFound,
+                        format!("no distribution with id {}", distribution_id),
+                    )
+                })?;
+            if distribution.package != pinned {
+                return Err(status::invalid_argument(format!(
+                    "distribution {} is of {}, but versionpin {} pins {}",
+                    distribution_id, distribution.package, versionpin_id, pinned
+                )));
+            }
+            distribution_id
+        }
+        (None, Some(version)) => find_distribution_id(tx, &pinned, version).await?,
+        _ => {
+            return Err(status::invalid_argument(
+                "a change must supply either a distribution_id or a version",
+            ))
+        }
+    };
+    Ok((versionpin_id, distribution_id))
+}
+
+/// Find the id of the versionpin for a package at exactly the supplied coords
+pub async fn find_versionpin_id(
+    tx: &Transaction<'_>,
+    package: &str,
+    coords: &Coords,
+) -> Result<IdType, Status> {
+    let mut pbd = PackratDb::new();
+    let mut results = pbd.find_all_versionpins();
+    results
+        .some_package(Some(package))
+        .level(coords.level.as_str())
+        .role(coords.role.as_str())
+        .platform(coords.platform.as_str())
+        .site(coords.site.as_str())
+        .search_mode(PLtreeSearchMode::Exact);
+    results
+        .query_tx(tx)
+        .await
+        .into_status()?
+        .into_iter()
+        .find(|row| to_coords(&row.coords) == *coords)
+        .map(|row| row.versionpin_id)
+        .ok_or_else(|| {
+            Status::new(
+                Code::NotFound,
+                format!("no versionpin for {} at {:?}", package, coords),
+            )
+        })
+}
+
+/// Find the id of the distribution of a package at the supplied version
+pub async fn find_distribution_id(
+    tx: &Transaction<'_>,
+    package: &str,
+    version: &str,
+) -> Result<IdType, Status> {
+    let mut pbd = PackratDb::new();
+    let mut results = pbd.find_all_distributions();
+    results
+        .package_opt(Some(package))
+        .version_opt(Some(version));
+    results
+        .query_tx(tx)
+        .await
+        .into_status()?
+        .into_iter()
+        .next()
+        .map(|FindAllDistributionsRow { id, .. }| id)
+        .ok_or_else(|| {
+            Status::new(
+                Code::NotFound,
+                format!("no distribution {}-{}", package, version),
+            )
+        })
+}
+
+/// Convert a FindVersionPinWithsRow into its protobuf representation
+pub fn to_versionpin_withs_row(row: FindVersionPinWithsRow) -> VersionPinWithsQueryRow {
+    let FindVersionPinWithsRow {
+        id,
+        vpin_id,
+        with,
+        order,
+    } = row;
+    VersionPinWithsQueryRow {
+        id: id as i64,
+        versionpin_id: vpin_id as i64,
+        with,
+        order,
+        resolved: None,
+    }
+}
+
+/// Retrieve the withs of a versionpin, in order, within a transaction
+pub async fn find_versionpin_withs(
+    tx: &Transaction<'_>,
+    versionpin_id: IdType,
+) -> Result<Vec<VersionPinWithsQueryRow>, Status> {
+    let mut pbd = PackratDb::new();
+    let mut results = pbd.find_versionpin_withs(versionpin_id);
+    let withs = results
+        .query_tx(tx)
+        .await
+        .into_status()?
+        .into_iter()
+        .map(to_versionpin_withs_row)
+        .collect::<Vec<_>>();
+    Ok(withs)
+}
+
+// Retrieve the names of the withs of a versionpin, in order
+async fn current_withs(tx: &Transaction<'_>, versionpin_id: IdType) -> Result<Vec<String>, Status> {
+    let mut withs = find_versionpin_withs(tx, versionpin_id).await?;
+    withs.sort_by_key(|row| row.order);
+    Ok(withs.into_iter().map(|row| row.with).collect())
+}
+
+/// Verify that each of the supplied names is a package known to the database,
+/// returning an InvalidArgument Status naming the first which is not.
+pub async fn validate_packages(tx: &Transaction<'_>, names: &[String]) -> Result<(), Status> {
+    let mut pbd = PackratDb::new();
+    let mut results = pbd.find_all_packages();
+    let packages = results
+        .query_tx(tx)
+        .await
+        .into_status()?
+        .into_iter()
+        .map(|FindAllPackagesRow { name }| name)
+        .collect::<Vec<_>>();
+    match names.iter().find(|name| !packages.contains(name)) {
+        Some(name) => Err(status::invalid_argument(format!(
+            "'{}' is not a known package",
+            name
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// The changes which may be made to the withs of a versionpin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WithsChange {
+    /// Append withs, which must be known packages the versionpin lacks
+    Add,
+    /// Reorder the withs, which must each be named once
+    Reorder,
+    /// Remove withs, which the versionpin must have
+    Remove,
+}
+
+/// Add, reorder or remove the withs of a versionpin within a changeset, once
+/// the caller has been authorized, and having validated the withs against
+/// those the versionpin currently has.
+///
+/// # Arguments
+///
+/// * `changeset` - The changeset in which to update the withs
+/// * `caller` - The caller updating the withs
+/// * `change` - Whether the withs are added, reordered or removed
+/// * `versionpin_id` - The id of the versionpin
+/// * `withs` - The withs added or removed, or the reordered withs
+///
+/// # Returns
+///
+/// * Result
+/// - Ok - The number of rows affected
+/// - Err - Status
+pub async fn update_withs(
+    changeset: &mut Changeset<'_>,
+    caller: &Caller,
+    change: WithsChange,
+    versionpin_id: IdType,
+    withs: Vec<String>,
+) -> Result<u64, Status> {
+    caller.authorize_levels(versionpin_levels(changeset.tx(), &[versionpin_id]).await?)?;
+    let current = current_withs(changeset.tx(), versionpin_id).await?;
+    let updated = match change {
+        WithsChange::Add => {
+            validate_packages(changeset.tx(), &withs).await?;
+            let mut updated = current;
+            for with in withs {
+                if updated.contains(&with) {
+                    return Err(Status::new(
+                        Code::AlreadyExists,
+                        format!("versionpin {} already has with {}", versionpin_id, with),
+                    ));
+                }
+                updated.push(with);
+            }
+            updated
+        }
+        WithsChange::Reorder => {
+            let mut current = current;
+            let mut requested = withs.clone();
+            current.sort();
+            requested.sort();
+            if current != requested {
+                return Err(status::invalid_argument(format!(
+                    "withs must name each of versionpin {}'s withs exactly once: {}",
+                    versionpin_id,
+                    current.join(",")
+                )));
+            }
+            withs
+        }
+        WithsChange::Remove => {
+            if let Some(missing) = withs.iter().find(|with| !current.contains(with)) {
+                return Err(Status::new(
+                    Code::NotFound,
+                    format!("versionpin {} has no with {}", versionpin_id, missing),
+                ));
+            }
+            current
+                .into_iter()
+                .filter(|with| !withs.contains(with))
+                .collect::<Vec<_>>()
+        }
+    };
+    changeset
+        .update_withs(versionpin_id, updated)
+        .await
+        .into_status()
+}
+
+/// Convert a FindAllPkgCoordsRow into its protobuf representation
+pub fn to_pkgcoords_row(row: FindAllPkgCoordsRow) -> PkgCoordsQueryRow {
+    let FindAllPkgCoordsRow {
+        id,
+        package,
+        level,
+        role,
+        platform,
+        site,
+    } = row;
+    PkgCoordsQueryRow {
+        id: id as i64,
+        package,
+        coords: Coords {
+            level,
+            role,
+            platform,
+            site,
+        },
+    }
+}
+
+/// Convert a FindAllDistributionsRow into its protobuf representation
+pub fn to_distributions_row(row: FindAllDistributionsRow) -> DistributionsQueryRow {
+    let FindAllDistributionsRow {
+        id,
+        package,
+        version,
+    } = row;
+    DistributionsQueryRow {
+        id: id as i64,
+        package,
+        version,
+    }
+}
+
+/// Retrieve the supplied pkgcoords, within a transaction. Ids which do not
+/// identify a pkgcoord are ignored.
+pub async fn find_pkgcoords_by_ids(
+    tx: &Transaction<'_>,
+    ids: &[IdType],
+) -> Result<Vec<FindAllPkgCoordsRow>, Status> {
+    let mut pbd = PackratDb::new();
+    let mut results = pbd.find_pkgcoords_by_ids(ids);
+    results.query_tx(tx).await.into_status()
+}
+
+/// Retrieve the supplied distributions, within a transaction. Ids which do not
+/// identify a distribution are ignored.
+pub async fn find_distributions_by_ids(
+    tx: &Transaction<'_>,
+    ids: &[IdType],
+) -> Result<Vec<FindAllDistributionsRow>, Status> {
+    let mut pbd = PackratDb::new();
+    let mut results = pbd.find_distributions_by_ids(ids);
+    results.query_tx(tx).await.into_status()
+}
+
+/// Delete versionpins within a changeset, once the caller has been
+/// authorized, and having verified that each exists and has no withs.
+///
+/// # Arguments
+///
+/// * `changeset` - The changeset in which to delete the versionpins
+/// * `caller` - The caller deleting the versionpins
+/// * `ids` - The ids of the versionpins
+///
+/// # Returns
+///
+/// * Result
+/// - Ok - The number of rows deleted, and the versionpins as they were
+/// - Err - Status
+pub async fn delete_versionpins(
+    changeset: &mut Changeset<'_>,
+    caller: &Caller,
+    mut ids: Vec<IdType>,
+) -> Result<(u64, Vec<VersionPinsQueryRow>), Status> {
+    let vpins = find_versionpins_by_ids(changeset.tx(), &ids).await?;
+    ensure_found(
+        "versionpin",
+        &ids,
+        vpins.iter().map(|row| row.versionpin_id),
+    )?;
+    caller.authorize_levels(vpins.iter().map(|row| row.coords.level.to_string()))?;
+    for id in &ids {
+        let withs = find_versionpin_withs(changeset.tx(), *id).await?;
+        refuse_orphans("versionpin", *id, "withs", withs.iter().map(|row| row.id))?;
+    }
+    let rows = changeset.delete_versionpins(&mut ids).await.into_status()?;
+    let vpins = vpins
+        .into_iter()
+        .map(to_versionpins_row)
+        .collect::<Vec<_>>();
+    Ok((rows, vpins))
+}
+
+/// Delete withs of a versionpin within a changeset, once the caller has been
+/// authorized, and having verified that each belongs to the versionpin.
+///
+/// # Arguments
+///
+/// * `changeset` - The changeset in which to delete the withs
+/// * `caller` - The caller deleting the withs
+/// * `versionpin_id` - The id of the versionpin the withs belong to
+/// * `ids` - The ids of the withs
+///
+/// # Returns
+///
+/// * Result
+/// - Ok - The number of rows deleted, and the withs as they were
+/// - Err - Status
+pub async fn delete_withs(
+    changeset: &mut Changeset<'_>,
+    caller: &Caller,
+    versionpin_id: IdType,
+    mut ids: Vec<IdType>,
+) -> Result<(u64, Vec<VersionPinWithsQueryRow>), Status> {
+    caller.authorize_levels(versionpin_levels(changeset.tx(), &[versionpin_id]).await?)?;
+    let withs = find_versionpin_withs(changeset.tx(), versionpin_id)
+        .await?
+        .into_iter()
+        .filter(|row| ids.iter().any(|id| *id as i64 == row.id))
+        .collect::<Vec<_>>();
+    ensure_found("with", &ids, withs.iter().map(|row| row.id as IdType))?;
+    let rows = changeset.delete_withs(&mut ids).await.into_status()?;
+    Ok((rows, withs))
+}
+
+/// Delete pkgcoords within a changeset, once the caller has been authorized,
+/// and having verified that each exists and is not referenced by a versionpin.
+///
+/// # Arguments
+///
+/// * `changeset` - The changeset in which to delete the pkgcoords
+/// * `caller` - The caller deleting the pkgcoords
+/// * `ids` - The ids of the pkgcoords
+///
+/// # Returns
+///
+/// * Result
+/// - Ok - The number of rows deleted, and the pkgcoords as they were
+/// - Err - Status
+pub async fn delete_pkgcoords(
+    changeset: &mut Changeset<'_>,
+    caller: &Caller,
+    mut ids: Vec<IdType>,
+) -> Result<(u64, Vec<PkgCoordsQueryRow>), Status> {
+    let pkgcoords = find_pkgcoords_by_ids(changeset.tx(), &ids).await?;
+    ensure_found("pkgcoord", &ids, pkgcoords.iter().map(|row| row.id))?;
+    caller.authorize_levels(pkgcoords.iter().map(|row| &row.level))?;
+    let mut pbd = PackratDb::new();
+    let mut results = pbd.find_versionpins_by_ids();
+    results.pkgcoord_ids(&ids);
+    let vpins = results.query_tx(changeset.tx()).await.into_status()?;
+    for id in &ids {
+        refuse_orphans(
+            "pkgcoord",
+            *id,
+            "versionpins",
+            vpins
+                .iter()
+                .filter(|row| row.pkgcoord_id == *id)
+                .map(|row| row.versionpin_id as i64),
+        )?;
+    }
+    let rows = changeset.delete_pkgcoords(&mut ids).await.into_status()?;
+    let pkgcoords = pkgcoords
+        .into_iter()
+        .map(to_pkgcoords_row)
+        .collect::<Vec<_>>();
+    Ok((rows, pkgcoords))
+}
+
+/// Delete distributions within a changeset, once the caller has been
+/// authorized, and having verified that each exists and is not referenced by
+/// a versionpin.
+///
+/// # Arguments
+///
+/// * `changeset` - The changeset in which to delete the distributions
+/// * `caller` - The caller deleting the distributions
+/// * `ids` - The ids of the distributions
+///
+/// # Returns
+///
+/// * Result
+/// - Ok - The number of rows deleted, and the distributions as they were
+/// - Err - Status
+pub async fn delete_distributions(
+    changeset: &mut Changeset<'_>,
+    caller: &Caller,
+    mut ids: Vec<IdType>,
+) -> Result<(u64, Vec<DistributionsQueryRow>), Status> {
+    caller.authorize(Scope::Global)?;
+    let distributions = find_distributions_by_ids(changeset.tx(), &ids).await?;
+    ensure_found("distribution", &ids, distributions.iter().map(|row| row.id))?;
+    let mut pbd = PackratDb::new();
+    let mut results = pbd.find_versionpins_by_ids();
+    results.distribution_ids(&ids);
+    let vpins = results.query_tx(changeset.tx()).await.into_status()?;
+    for id in &ids {
+        refuse_orphans(
+            "distribution",
+            *id,
+            "versionpins",
+            vpins
+                .iter()
+                .filter(|row| row.distribution_id == *id)
+                .map(|row| row.versionpin_id as i64),
+        )?;
+    }
+    let rows = changeset
+        .delete_distributions(&mut ids)
+        .await
+        .into_status()?;
+    let distributions = distributions
+        .into_iter()
+        .map(to_distributions_row)
+        .collect::<Vec<_>>();
+    Ok((rows, distributions))
+}
+
+/// Verify that a row was found for each of the requested ids, returning a
+/// NotFound Status naming those which were not.
+pub fn ensure_found<I>(noun: &str, ids: &[IdType], found: I) -> Result<(), Status>
+where
+    I: IntoIterator<Item = IdType>,
+{
+    let found = found.into_iter().collect::<Vec<_>>();
+    let missing = ids
+        .iter()
+        .filter(|id| !found.contains(id))
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(Status::new(
+            Code::NotFound,
+            format!("no {} with id {}", noun, missing.join(",")),
+        ))
+    }
+}
+
+/// Refuse to delete a row which is still referenced by dependent rows,
+/// returning a FailedPrecondition Status naming the dependents.
+pub fn refuse_orphans<I>(noun: &str, id: IdType, dependents: &str, ids: I) -> Result<(), Status>
+where
+    I: IntoIterator<Item = i64>,
+{
+    let ids = ids.into_iter().map(|id| id.to_string()).collect::<Vec<_>>();
+    if ids.is_empty() {
+        Ok(())
+    } else {
+        Err(Status::new(
+            Code::FailedPrecondition,
+            format!(
+                "{} {} is referenced by {} {}",
+                noun,
+                id,
+                dependents,
+                ids.join(",")
+            ),
+        ))
+    }
+}
+
+// Prefix the message of a Status returned while processing an op of a
+// changeset with the op's index, preserving its code
+fn in_op(index: usize, status: Status) -> Status {
+    Status::new(status.code(), format!("op {}: {}", index, status.message()))
+}
+
+/// An op of a changeset, with any entities it references by name resolved
+/// to ids, ready to apply within a transaction.
+#[derive(Debug)]
+pub enum ChangesetAction {
+    AddPackages(Vec<String>),
+    AddLevels(Vec<String>),
+    AddRoles(Vec<String>),
+    AddPlatforms(Vec<String>),
+    AddVersionPins {
+        package: String,
+        version: String,
+        levels: Vec<String>,
+        roles: Vec<String>,
+        platforms: Vec<String>,
+        sites: Vec<String>,
+    },
+    SetVersionPin {
+        versionpin_id: IdType,
+        distribution_id: IdType,
+    },
+    UpdateWiths {
+        change: WithsChange,
+        versionpin_id: IdType,
+        withs: Vec<String>,
+    },
+    DeleteVersionPins(Vec<IdType>),
+    DeleteWiths {
+        versionpin_id: IdType,
+        ids: Vec<IdType>,
+    },
+    DeletePkgCoords(Vec<IdType>),
+    DeleteDistributions(Vec<IdType>),
+}
+
+impl ChangesetAction {
+    /// Validate an op, and resolve any entities it references by name.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The changeset's transaction, used to resolve the op's references
+    /// * `op` - The op, as received in the request
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - ChangesetAction
+    /// - Err - Status
+    pub async fn resolve(tx: &Transaction<'_>, op: Op) -> Result<Self, Status> {
+        let action = match op {
+            Op::AddPackages(NamesOp { names }) => {
+                ChangesetAction::AddPackages(non_empty(names, "packages")?)
+            }
+            Op::AddLevels(NamesOp { names }) => {
+                ChangesetAction::AddLevels(non_empty(names, "levels")?)
+            }
+            Op::AddRoles(NamesOp { names }) => {
+                ChangesetAction::AddRoles(non_empty(names, "roles")?)
+            }
+            Op::AddPlatforms(NamesOp { names }) => {
+                ChangesetAction::AddPlatforms(non_empty(names, "platforms")?)
+            }
+            Op::AddVersionpins(VersionPinsAddOp {
+                package,
+                version,
+                levels,
+                roles,
+                platforms,
+                sites,
+            }) => ChangesetAction::AddVersionPins {
+                package,
+                version,
+                levels: or_default(levels, "facility"),
+                roles: or_default(roles, "any"),
+                platforms: or_default(platforms, "any"),
+                sites: or_default(sites, "any"),
+            },
+            Op::SetVersionpin(change) => {
+                let (versionpin_id, distribution_id) =
+                    resolve_versionpin_change(tx, change).await?;
+                ChangesetAction::SetVersionPin {
+                    versionpin_id,
+                    distribution_id,
+                }
+            }
+            Op::AddWiths(op) => to_update_withs(WithsChange::Add, op)?,
+            Op::ReorderWiths(op) => to_update_withs(WithsChange::Reorder, op)?,
+            Op::RemoveWiths(op) => to_update_withs(WithsChange::Remove, op)?,
+            Op::DeleteVersionpins(IdsOp { ids }) => {
+                ChangesetAction::DeleteVersionPins(non_empty(to_ids("ids", &ids)?, "versionpins")?)
+            }
+            Op::DeleteWiths(WithsDeleteOp { versionpin_id, ids }) => ChangesetAction::DeleteWiths {
+                versionpin_id: to_id("versionpin_id", versionpin_id)?,
+                ids: non_empty(to_ids("ids", &ids)?, "withs")?,
+            },
+            Op::DeletePkgcoords(IdsOp { ids }) => {
+                ChangesetAction::DeletePkgCoords(non_empty(to_ids("ids", &ids)?, "pkgcoords")?)
+            }
+            Op::DeleteDistributions(IdsOp { ids }) => ChangesetAction::DeleteDistributions(
+                non_empty(to_ids("ids", &ids)?, "distributions")?,
+            ),
+        };
+        Ok(action)
+    }
+
+    /// Verify that the caller may apply the action, looking up the level of
+    /// any versionpin it updates. Withs and deletions are authorized by
+    /// `apply`.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The changeset's transaction, used to look up levels
+    /// * `caller` - The caller applying the changeset
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - ()
+    /// - Err - Status
+    pub async fn authorize(&self, tx: &Transaction<'_>, caller: &Caller) -> Result<(), Status> {
+        match self {
+            ChangesetAction::AddPackages(_)
+            | ChangesetAction::AddRoles(_)
+            | ChangesetAction::AddPlatforms(_) => caller.authorize(Scope::Global),
+            ChangesetAction::AddLevels(levels) | ChangesetAction::AddVersionPins { levels, .. } => {
+                caller.authorize_levels(levels)
+            }
+            ChangesetAction::SetVersionPin { versionpin_id, .. } => {
+                caller.authorize_levels(versionpin_levels(tx, &[*versionpin_id]).await?)
+            }
+            // withs and deletions are authorized as they are applied,
+            // alongside the lookups of the rows they change
+            ChangesetAction::UpdateWiths { .. }
+            | ChangesetAction::DeleteVersionPins(_)
+            | ChangesetAction::DeleteWiths { .. }
+            | ChangesetAction::DeletePkgCoords(_)
+            | ChangesetAction::DeleteDistributions(_) => Ok(()),
+        }
+    }
+
+    /// Apply the action within a changeset, which records a single revision
+    /// for all of its actions once committed, and report the number of rows
+    /// the action added, updated or deleted. Withs and deletions share the
+    /// checks of the standalone rpcs, so that deletions which would orphan
+    /// dependent rows are refused.
+    ///
+    /// # Arguments
+    ///
+    /// * `changeset` - The changeset in which to apply the action
+    /// * `caller` - The caller applying the changeset
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - The number of rows affected
+    /// - Err - Status
+    pub async fn apply(
+        self,
+        changeset: &mut Changeset<'_>,
+        caller: &Caller,
+    ) -> Result<u64, Status> {
+        let rows = match self {
+            ChangesetAction::AddPackages(mut names) => {
+                changeset.add_packages(&mut names).await.into_status()?
+            }
+            ChangesetAction::AddLevels(mut names) => {
+                changeset.add_levels(&mut names).await.into_status()?
+            }
+            ChangesetAction::AddRoles(mut names) => {
+                changeset.add_roles(&mut names).await.into_status()?
+            }
+            ChangesetAction::AddPlatforms(mut names) => {
+                changeset.add_platforms(&mut names).await.into_status()?
+            }
+            ChangesetAction::AddVersionPins {
+                package,
+                version,
+                mut levels,
+                mut roles,
+                mut platforms,
+                mut sites,
+            } => changeset
+                .add_versionpins(
+                    package,
+                    version,
+                    &mut levels,
+                    &mut roles,
+                    &mut platforms,
+                    &mut sites,
+                )
+                .await
+                .into_status()?
+                // the ids of the versionpins added
+                .len() as u64,
+            ChangesetAction::SetVersionPin {
+                versionpin_id,
+                distribution_id,
+            } => changeset
+                .update_versionpin(versionpin_id, distribution_id)
+                .await
+                .into_status()?,
+            ChangesetAction::UpdateWiths {
+                change,
+                versionpin_id,
+                withs,
+            } => update_withs(changeset, caller, change, versionpin_id, withs).await?,
+            ChangesetAction::DeleteVersionPins(ids) => {
+                delete_versionpins(changeset, caller, ids).await?.0
+            }
+            ChangesetAction::DeleteWiths { versionpin_id, ids } => {
+                delete_withs(changeset, caller, versionpin_id, ids).await?.0
+            }
+            ChangesetAction::DeletePkgCoords(ids) => {
+                delete_pkgcoords(changeset, caller, ids).await?.0
+            }
+            ChangesetAction::DeleteDistributions(ids) => {
+                delete_distributions(changeset, caller, ids).await?.0
+            }
+        };
+        Ok(rows)
+    }
+}
+
+// Validate an op changing the withs of a versionpin
+fn to_update_withs(change: WithsChange, op: WithsOp) -> Result<ChangesetAction, Status> {
+    let WithsOp {
+        versionpin_id,
+        withs,
+    } = op;
+    Ok(ChangesetAction::UpdateWiths {
+        change,
+        versionpin_id: to_id("versionpin_id", versionpin_id)?,
+        withs: non_empty(withs, "withs")?,
+    })
+}
+
+// Reject an op which does not name any entities
+fn non_empty<T>(values: Vec<T>, noun: &str) -> Result<Vec<T>, Status> {
+    if values.is_empty() {
+        Err(status::invalid_argument(format!("no {} supplied", noun)))
+    } else {
+        Ok(values)
+    }
+}
+
+/// The parameters of a query for versionpins, extracted from a
+/// VersionPinsQueryRequest.
+#[derive(Debug)]
+pub struct VersionPinsQuery {
+    package: Option<String>,
+    version: Option<String>,
+    level: String,
+    role: String,
+    platform: String,
+    site: String,
+    isolate_facility: bool,
+    search_mode: LtreeSearchMode,
+    order_by: Vec<SearchAttribute>,
+    order_direction: Option<OrderDirection>,
+    full_withs: bool,
+    limit: Option<IdType>,
+    page_size: Option<u32>,
+    cursor: Option<PageToken>,
+    fingerprint: u64,
+}
+
+impl VersionPinsQuery {
+    /// Extract the query parameters from a request, applying defaults for
+    /// those which are absent.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The VersionPinsQueryRequest
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - VersionPinsQuery
+    /// - Err - Status
+    pub fn from_request(request: VersionPinsQueryRequest) -> Result<Self, Status> {
+        let VersionPinsQueryRequest {
+            package,
+            version,
+            level,
+            role,
+            platform,
+            site,
+            full_withs,
+            isolate_facility,
+            limit,
+            page_size,
+            page_token,
+            search_mode,
+            order_by,
+            order_direction,
+        } = request;
+        let fingerprint = page_token::fingerprint(&(
+            &package,
+            &version,
+            &level,
+            &role,
+            &platform,
+            &site,
+            &isolate_facility,
+            &search_mode,
+            &order_by,
+            &order_direction,
+            &full_withs,
+            &limit,
+        ));
+        let cursor = PageToken::resume(page_token.as_deref(), fingerprint)?;
+        let order_by = parse_search_attributes(&order_by)?;
+        if let Some(cursor) = &cursor {
+            if cursor.values().len() != order_by.len() {
+                return Err(status::invalid_argument(
+                    "page_token does not match the query's order_by",
+                ));
+            }
+        }
+        let (level, role, platform, site) = extract_coords(level, role, platform, site);
+        Ok(Self {
+            package,
+            version,
+            level,
+            role,
+            platform,
+            site,
+            isolate_facility: isolate_facility.unwrap_or(false),
+            search_mode: parse_search_mode(search_mode)?,
+            order_by,
+            order_direction: parse_order_direction(order_direction)?,
+            full_withs: full_withs.unwrap_or(false),
+            limit: limit
+                .map(|limit| to_id("limit", i64::from(limit)))
+                .transpose()?,
+            page_size: page_size.filter(|size| *size > 0),
+            cursor,
+            fingerprint,
+        })
+    }
+
+    /// Whether the request asked for a page of the results
+    pub fn is_paged(&self) -> bool {
+        self.page_size.is_some() || self.cursor.is_some()
+    }
+
+    // The number of rows prior pages have returned
+    fn returned(&self) -> u64 {
+        self.cursor.as_ref().map_or(0, PageToken::returned)
+    }
+
+    // The maximum number of rows this page may return, honouring the limit
+    // across all pages, or None if unbounded
+    fn page_limit(&self) -> Option<u64> {
+        let remaining = self
+            .limit
+            .map(|limit| (limit.max(0) as u64).saturating_sub(self.returned()));
+        match (self.page_size.map(u64::from), remaining) {
+            (Some(size), Some(remaining)) => Some(size.min(remaining)),
+            (size, remaining) => size.or(remaining),
+        }
+    }
+
+    // The number of rows to fetch. A page fetches one row beyond its size, to
+    // discover whether there is a next page without querying for it.
+    fn fetch_limit(&self) -> Option<IdType> {
+        self.page_limit().map(|limit| {
+            let limit = if self.page_size.is_some() {
+                limit + 1
+            } else {
+                limit
+            };
+            limit.min(IdType::max_value() as u64) as IdType
+        })
+    }
+
+    // The values of the columns the query is ordered by, for a row
+    fn cursor_values(&self, row: &FindAllVersionPinsRow) -> Vec<String> {
+        self.order_by
+            .iter()
+            .map(|attr| match attr {
+                SearchAttribute::Package => row.distribution.package().to_string(),
+                SearchAttribute::Level => row.coords.level.to_string(),
+                SearchAttribute::Role => row.coords.role.to_string(),
+                SearchAttribute::Platform => row.coords.platform.to_string(),
+                SearchAttribute::Site => row.coords.site.to_string(),
+            })
+            .collect()
+    }
+
+    /// Trim the rows fetched for a page to the page's size, returning the
+    /// token of the next page if the query fetched a row beyond it. The token
+    /// records the cursor of the last row of the page.
+    ///
+    /// # Arguments
+    ///
+    /// * `rows` - The rows fetched by the configured query
+    ///
+    /// # Returns
+    ///
+    /// * Option wrapped next_page_token
+    pub fn next_page_token(&self, rows: &mut Vec<FindAllVersionPinsRow>) -> Option<String> {
+        let page_limit = self.page_limit()?;
+        if self.page_size.is_none() || rows.len() as u64 <= page_limit {
+            return None;
+        }
+        rows.truncate(page_limit as usize);
+        let last = rows.last()?;
+        Some(
+            PageToken::new(
+                self.cursor_values(last),
+                last.versionpin_id,
+                self.returned() + page_limit,
+                self.fingerprint,
+            )
+            .encode(),
+        )
+    }
+
+    /// Convert a row returned by the query into its protobuf representation,
+    /// resolving its withs to distributions if full_withs was requested.
+    pub async fn to_row(
+        &self,
+        client: &Client,
+        row: FindAllVersionPinsRow,
+        withs: &mut WithsResolver,
+    ) -> Result<VersionPinsQueryRow, Status> {
+        let mut row = to_versionpins_row(row);
+        if self.full_withs {
+            row.withs = withs.resolve(client, &row.withs, &row.coords).await?;
+        }
+        Ok(row)
+    }
+
+    /// Apply the query parameters to a find_all_versionpins query. The limit
+    /// is applied by the query itself, so that rows beyond it are neither
+    /// fetched nor have their withs resolved. Rows are always ordered by
+    /// versionpin_id after any requested ordering, so that a page's cursor
+    /// identifies a single position, and a paged query selects only the rows
+    /// ordered after the cursor of the page token, if any.
+    pub fn configure<'a>(&'a self, results: &mut FindAllVersionPins<'a>) {
+        results
+            .some_package(self.package.as_deref())
+            .some_version(self.version.as_deref())
+            .level(self.level.as_str())
+            .isolate_facility(self.isolate_facility)
+            .role(self.role.as_str())
+            .platform(self.platform.as_str())
+            .site(self.site.as_str())
+            .search_mode(self.search_mode.into())
+            .limit_opt(self.fetch_limit());
+        if !self.order_by.is_empty() {
+            results.order_by(
+                self.order_by
+                    .iter()
+                    .map(|attr| PSearchAttribute::from(*attr))
+                    .collect(),
+            );
+        }
+        if let Some(direction) = self.order_direction {
+            results.order_direction(direction.into());
+        }
+        results.then_by_versionpin_id();
+        if let Some(cursor) = &self.cursor {
+            results.after(cursor.values().to_vec(), cursor.id());
+        }
+    }
+
+    /// Run the query, sending each row to `tx` as it comes off the cursor
+    /// opened by packybara's `FindAllVersionPins::query_stream`, so that the
+    /// rows are never collected in memory. Stops early, without error, if
+    /// the receiver hangs up.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - The pool from which to check out a connection
+    /// * `tx` - The sending half of the channel backing the response stream
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - ()
+    /// - Err - Status
+    pub async fn stream(
+        &self,
+        pool: &ConnectionPool,
+        tx: &mut mpsc::Sender<Result<VersionPinsQueryRow, Status>>,
+    ) -> Result<(), Status> {
+        let client = pool.get().await.map_err(|e| status::from_pool_error(&e))?;
+        let mut pbd = PackratDb::new();
+        let mut results = pbd.find_all_versionpins();
+        self.configure(&mut results);
+        let rows = results.query_stream(&client).await.into_status()?;
+        let mut withs = WithsResolver::new();
+        tokio::pin!(rows);
+        while let Some(row) = rows.next().await {
+            let row = self.to_row(&client, row.into_status()?, &mut withs).await?;
+            if tx.send(Ok(row)).await.is_err() {
+                log::debug!("client hung up before the versionpins stream completed");
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Resolves the withs of versionpins to the distributions they are pinned to
+/// at the versionpins' coords. Resolutions are cached, as the same withs
+/// recur across the rows of a query.
+#[derive(Debug, Default)]
+pub struct WithsResolver {
+    cache: HashMap<(String, String, String, String, String), WithsQueryRow>,
+}
+
+impl WithsResolver {
+    /// New up an empty WithsResolver
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolve each of the supplied with packages to the distribution it is
+    /// pinned to at the supplied coords.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The connection used to resolve the withs
+    /// * `withs` - The names of the with packages
+    /// * `coords` - The coords of the versionpin the withs belong to
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - The distributions, in the same order as the withs
+    /// - Err - Status
+    pub async fn resolve(
+        &mut self,
+        client: &Client,
+        withs: &[String],
+        coords: &Coords,
+    ) -> Result<Vec<String>, Status> {
+        let distributions = self
+            .resolve_rows(client, withs, coords)
+            .await?
+            .into_iter()
+            .map(|row| row.distribution)
+            .collect::<Vec<_>>();
+        Ok(distributions)
+    }
+
+    /// Resolve each of the supplied with packages to the versionpin which
+    /// applies to it at the supplied coords, as GetWiths does.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The connection used to resolve the withs
+    /// * `withs` - The names of the with packages
+    /// * `coords` - The coords of the versionpin the withs belong to
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - The id, distribution and coords of each with's versionpin, in
+    /// the same order as the withs
+    /// - Err - Status
+    pub async fn resolve_rows(
+        &mut self,
+        client: &Client,
+        withs: &[String],
+        coords: &Coords,
+    ) -> Result<Vec<WithsQueryRow>, Status> {
+        let mut rows = Vec::with_capacity(withs.len());
+        for with in withs {
+            let key = (
+                with.clone(),
+                coords.level.clone(),
+                coords.role.clone(),
+                coords.platform.clone(),
+                coords.site.clone(),
+            );
+            if let Some(row) = self.cache.get(&key) {
+                rows.push(row.clone());
+                continue;
+            }
+            let mut pbd = PackratDb::new();
+            let FindVersionPinsRow {
+                versionpin_id,
+                distribution,
+                coords: resolved,
+                ..
+            } = pbd
+                .find_versionpin(with.as_str())
+                .level(coords.level.as_str())
+                .role(coords.role.as_str())
+                .platform(coords.platform.as_str())
+                .site(coords.site.as_str())
+                .query(client)
+                .await
+                .into_status()?;
+            let row = WithsQueryRow {
+                versionpin_id: versionpin_id as i64,
+                distribution: distribution.to_string(),
+                coords: to_coords(&resolved),
+            };
+            self.cache.insert(key, row.clone());
+            rows.push(row);
+        }
+        Ok(rows)
+    }
 }
@@ -0,0 +1,197 @@
+//! Mapping of errors encountered while servicing a request onto grpc Status codes.
+//!
+//! Errors are classified by walking their source chain:
+//!
+//! * `tokio_postgres::Error` - classified by sql state. Data exceptions and
+//!   syntax errors (eg a malformed levelspec) are `InvalidArgument`,
+//!   connection exceptions or a closed connection are `Unavailable`.
+//! * `std::io::Error` - `Unavailable`
+//! * packybara's `FindVersionPinsError::NoQueryResults`, reported when no
+//!   versionpin applies at the requested coords - `NotFound`
+//! * anything else - `Internal`
+use crate::pool::PoolError;
+use bb8::RunError;
+use packybara::db::find::versionpins::FindVersionPinsError;
+use std::error::Error as StdError;
+use tonic::{Code, Status};
+
+/// Convert the error carried by a Result into a Status
+pub trait IntoStatus<T> {
+    /// Map the error, if any, into a Status with an appropriate Code
+    fn into_status(self) -> Result<T, Status>;
+}
+
+impl<T, E> IntoStatus<T> for Result<T, E>
+where
+    E: StdError + 'static,
+{
+    fn into_status(self) -> Result<T, Status> {
+        self.map_err(|e| from_error(&e))
+    }
+}
+
+/// Build a Status from an error, classifying it by walking its source chain.
+///
+/// # Arguments
+///
+/// * `err` - Reference to the error
+///
+/// # Returns
+///
+/// * Status
+pub fn from_error<E>(err: &E) -> Status
+where
+    E: StdError + 'static,
+{
+    let code = classify(err);
+    log_status(code, err);
+    Status::new(code, format!("{}", err))
+}
+
+/// Build a Status from an error encountered while checking out a connection
+/// from the pool.
+pub fn from_pool_error(err: &PoolError) -> Status {
+    let code = match err {
+        RunError::User(e) => classify(e),
+        RunError::TimedOut => Code::Unavailable,
+    };
+    // a pool which cannot hand out connections is unavailable, regardless of cause
+    let code = if code == Code::Internal {
+        Code::Unavailable
+    } else {
+        code
+    };
+    log_status(code, err);
+    Status::new(
+        code,
+        format!("unable to check out database connection: {}", err),
+    )
+}
+
+/// Build a Status with the InvalidArgument code
+pub fn invalid_argument<I>(msg: I) -> Status
+where
+    I: Into<String>,
+{
+    Status::new(Code::InvalidArgument, msg)
+}
+
+fn log_status(code: Code, err: &dyn std::fmt::Display) {
+    match code {
+        Code::Internal | Code::Unavailable => log::error!("{:?}: {}", code, err),
+        _ => log::debug!("{:?}: {}", code, err),
+    }
+}
+
+fn classify(err: &(dyn StdError + 'static)) -> Code {
+    let mut current = Some(err);
+    while let Some(e) = current {
+        if let Some(db_err) = e.downcast_ref::<tokio_postgres::Error>() {
+            if let Some(code) = classify_db_error(db_err) {
+                return code;
+            }
+        } else if e.is::<std::io::Error>() {
+            return Code::Unavailable;
+        } else if let Some(FindVersionPinsError::NoQueryResults) = e.downcast_ref() {
+            return Code::NotFound;
+        }
+        current = e.source();
+    }
+    Code::Internal
+}
+
+// Returns None if the error carries no sql state and is not a connection
+// failure, so that classification may continue down the source chain.
+fn classify_db_error(err: &tokio_postgres::Error) -> Option<Code> {
+    if err.is_closed() {
+        return Some(Code::Unavailable);
+    }
+    err.code().map(|state| classify_sqlstate(state.code()))
+}
+
+fn classify_sqlstate(state: &str) -> Code {
+    match state {
+        // syntax_error - eg an ltree which fails to parse
+        "42601" => Code::InvalidArgument,
+        // unique_violation
+        "23505" => Code::AlreadyExists,
+        // serialization_failure, deadlock_detected
+        "40001" | "40P01" => Code::Aborted,
+        // admin_shutdown, crash_shutdown, cannot_connect_now
+        "57P01" | "57P02" | "57P03" => Code::Unavailable,
+        // data_exception
+        s if s.starts_with("22") => Code::InvalidArgument,
+        // integrity_constraint_violation
+        s if s.starts_with("23") => Code::FailedPrecondition,
+        // connection_exception
+        s if s.starts_with("08") => Code::Unavailable,
+        _ => Code::Internal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt;
+
+    #[derive(Debug)]
+    struct Wrapper {
+        msg: &'static str,
+        source: Option<std::io::Error>,
+    }
+
+    impl fmt::Display for Wrapper {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.msg)
+        }
+    }
+
+    impl StdError for Wrapper {
+        fn source(&self) -> Option<&(dyn StdError + 'static)> {
+            self.source.as_ref().map(|e| e as &(dyn StdError + 'static))
+        }
+    }
+
+    #[test]
+    fn io_error_in_chain_is_unavailable() {
+        let err = Wrapper {
+            msg: "query failed",
+            source: Some(std::io::Error::new(
+                std::io::ErrorKind::ConnectionReset,
+                "reset",
+            )),
+        };
+        assert_eq!(from_error(&err).code(), Code::Unavailable);
+    }
+
+    #[test]
+    fn no_query_results_is_not_found() {
+        let err = FindVersionPinsError::NoQueryResults;
+        assert_eq!(from_error(&err).code(), Code::NotFound);
+        // recognized by variant, rather than by message
+        let err = Wrapper {
+            msg: "No Query Results",
+            source: None,
+        };
+        assert_eq!(from_error(&err).code(), Code::Internal);
+    }
+
+    #[test]
+    fn unknown_error_is_internal() {
+        let err = Wrapper {
+            msg: "something went sideways",
+            source: None,
+        };
+        assert_eq!(from_error(&err).code(), Code::Internal);
+    }
+
+    #[test]
+    fn can_classify_sqlstate() {
+        assert_eq!(classify_sqlstate("42601"), Code::InvalidArgument);
+        assert_eq!(classify_sqlstate("22P02"), Code::InvalidArgument);
+        assert_eq!(classify_sqlstate("23503"), Code::FailedPrecondition);
+        assert_eq!(classify_sqlstate("23505"), Code::AlreadyExists);
+        assert_eq!(classify_sqlstate("08006"), Code::Unavailable);
+        assert_eq!(classify_sqlstate("XX000"), Code::Internal);
+    }
+}
@@ -0,0 +1,115 @@
+//! Tracking of the tasks spawned to feed streaming responses.
+//!
+//! Streaming rpcs (eg `StreamVersionPins`, or the health service's `Watch`)
+//! hand their response stream to tonic, and feed it from a task of their own.
+//! Those tasks outlive the request handler, and hold handles to the
+//! connection pool, so the server stops them, and waits for them to finish,
+//! before closing the pool.
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, watch};
+use tonic::{Code, Status};
+
+/// Spawns tasks which may be stopped, and waited for, as the server shuts down.
+/// Clones share the same set of tasks.
+#[derive(Debug, Clone)]
+pub struct Tasks {
+    running: Arc<Mutex<Option<Running>>>,
+    stop: watch::Receiver<bool>,
+}
+
+// Shared state, taken when the tasks are stopped
+#[derive(Debug)]
+struct Running {
+    // cloned into each task, so that `done` ends once every task has finished
+    running: mpsc::Sender<()>,
+    done: mpsc::Receiver<()>,
+    stop: watch::Sender<bool>,
+}
+
+impl Tasks {
+    /// New up a Tasks instance, with no tasks running
+    pub fn new() -> Self {
+        let (running, done) = mpsc::channel(1);
+        let (stop_tx, stop) = watch::channel(false);
+        Self {
+            running: Arc::new(Mutex::new(Some(Running {
+                running,
+                done,
+                stop: stop_tx,
+            }))),
+            stop,
+        }
+    }
+
+    /// Spawn a task, which is abandoned at its next await point once the
+    /// tasks are stopped. Once stopping has begun, no further tasks may be
+    /// spawned, and an Unavailable Status is returned instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The future to run
+    ///
+    /// # Returns
+    ///
+    /// * Result
+    /// - Ok - ()
+    /// - Err - Status
+    pub fn spawn<F>(&self, task: F) -> Result<(), Status>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let running = match *self.running.lock().expect("tasks lock poisoned") {
+            Some(Running { ref running, .. }) => running.clone(),
+            None => {
+                return Err(Status::new(
+                    Code::Unavailable,
+                    "the server is shutting down",
+                ))
+            }
+        };
+        let stop = self.stop.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = task => {}
+                _ = stopped(stop) => log::debug!("stopped a streaming task"),
+            }
+            drop(running);
+        });
+        Ok(())
+    }
+
+    /// Stop the tasks, and wait for each of them to finish. Once this
+    /// returns, the tasks have dropped everything they held.
+    pub async fn stop(&self) {
+        let running = self.running.lock().expect("tasks lock poisoned").take();
+        let Running {
+            running,
+            mut done,
+            stop,
+        } = match running {
+            Some(running) => running,
+            None => return,
+        };
+        drop(running);
+        // the tasks may all have finished, dropping their receivers
+        let _ = stop.broadcast(true);
+        // nothing is ever sent, so this resolves once every sender is dropped
+        while done.recv().await.is_some() {}
+    }
+}
+
+impl Default for Tasks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Resolves once the tasks have been asked to stop
+async fn stopped(mut stop: watch::Receiver<bool>) {
+    while let Some(stopped) = stop.recv().await {
+        if stopped {
+            return;
+        }
+    }
+}